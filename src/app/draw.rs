@@ -4,7 +4,6 @@ use client_entity::CompRenderable;
 impl App {
     pub fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
         self.framebuffer_size = framebuffer.size();
-        self.light = light::Uniforms::new(&self.view);
 
         ugli::clear(framebuffer, Some(Color::BLACK), Some(1.0));
         self.camera_controls.draw(&mut self.camera, framebuffer);
@@ -28,9 +27,8 @@ impl App {
             self.draw_circle(framebuffer, pos, entity.size.unwrap(), Color::GREEN);
         }
         if let Some(pos) = selected_pos {
-            if let Some(entity) = self.view.get_closest_entity(pos) {
+            if let Some(entity) = self.get_closest_entity(pos) {
                 if entity.id != self.player_id {
-                    let entity = self.entities.get(&entity.id).unwrap();
                     self.draw_circle(
                         framebuffer,
                         entity.pos.unwrap(),
@@ -184,6 +182,20 @@ impl App {
                 );
             } else {
                 text = entity.entity_type.to_string();
+                if let Some(container) = &entity.container {
+                    let items: Vec<String> = container
+                        .items
+                        .iter()
+                        .map(|item| item.entity_type.to_string())
+                        .collect();
+                    text = format!(
+                        "{} ({}/{}) {}",
+                        text,
+                        container.items.len(),
+                        container.capacity,
+                        items.join(", ")
+                    );
+                }
                 pos = entity.pos.unwrap().extend(
                     self.tile_mesh
                         .get_height(entity.pos.unwrap())
@@ -235,6 +247,60 @@ impl App {
             32.0,
             Color::WHITE,
         );
+        if let Some(player) = self.entities.get(&self.player_id) {
+            let mut pos = vec2(32.0, self.framebuffer_size.y as f32 - 64.0);
+            for recipe in self
+                .pack_data
+                .recipes
+                .iter()
+                .filter(|recipe| recipe.is_relevant(player))
+            {
+                self.geng.default_font().draw(
+                    framebuffer,
+                    &geng::PixelPerfectCamera,
+                    &recipe.to_string(),
+                    pos,
+                    24.0,
+                    Color::WHITE,
+                );
+                pos.y -= 32.0;
+            }
+        }
+        if let Some(player) = self.entities.get(&self.player_id) {
+            if let Some(inventory) = &player.inventory {
+                let slot_width = 160.0;
+                let mut pos = vec2(
+                    (self.framebuffer_size.x as f32 - slot_width * inventory.slots as f32) / 2.0,
+                    32.0,
+                );
+                for (slot, item) in inventory.items.iter().enumerate() {
+                    let (item, color) = if slot == inventory.active_slot {
+                        match &player.holding {
+                            Some(holding) => (&holding.entity, Color::YELLOW),
+                            None => {
+                                pos.x += slot_width;
+                                continue;
+                            }
+                        }
+                    } else {
+                        (item, Color::WHITE)
+                    };
+                    let text = match item {
+                        Some(item) => format!("{}: {}", slot + 1, item.entity_type),
+                        None => format!("{}: -", slot + 1),
+                    };
+                    self.geng.default_font().draw(
+                        framebuffer,
+                        &geng::PixelPerfectCamera,
+                        &text,
+                        pos,
+                        24.0,
+                        color,
+                    );
+                    pos.x += slot_width;
+                }
+            }
+        }
         self.geng.default_font().draw(
             framebuffer,
             &geng::PixelPerfectCamera,
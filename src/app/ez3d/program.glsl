@@ -1,4 +1,7 @@
 varying vec4 v_color;
+varying vec3 v_light;
+varying float v_emission;
+varying vec2 v_world_pos;
 
 #ifdef VERTEX_SHADER
 attribute vec3 a_pos;
@@ -15,6 +18,7 @@ uniform mat4 u_projection_matrix;
 uniform mat4 u_view_matrix;
 
 uniform vec3 u_light_direction;
+uniform float u_daylight;
 
 #define AMBIENT vec3(0.3, 0.3, 0.3)
 #define AMBIENT2 vec3(0.2, 0.2, 0.2)
@@ -26,15 +30,45 @@ void main()
     vec3 normal = a_normal;
     normal.xy = rotate(normal.xy, i_rotation);
     vec3 light = AMBIENT + max(0.0, dot(a_normal, vec3(0.0, 0.0, 1.0))) * AMBIENT2 + max(0.0, dot(a_normal, u_light_direction)) * (vec3(1.0) - AMBIENT - AMBIENT2);
-    vec4 color = a_color * i_color;
-    v_color = vec4(color.xyz * min(light + vec3(a_emission), vec3(1.0)), color.w);
-    gl_Position = u_projection_matrix * u_view_matrix * vec4(i_pos + pos * i_size, 1.0);
+    v_light = light * u_daylight;
+    v_emission = a_emission;
+    v_color = a_color * i_color;
+    vec3 world_pos = i_pos + pos * i_size;
+    v_world_pos = world_pos.xy;
+    gl_Position = u_projection_matrix * u_view_matrix * vec4(world_pos, 1.0);
 }
 #endif
 
 #ifdef FRAGMENT_SHADER
+uniform sampler2D u_light_map;
+uniform vec2 u_light_map_pos;
+uniform vec2 u_light_map_size;
+uniform vec3 u_moving_light0;
+uniform vec3 u_moving_light1;
+uniform vec3 u_moving_light2;
+uniform vec3 u_moving_light3;
+
+#define LIGHT_COLOR vec3(1.0, 0.8, 0.5)
+
+// x and y are the position of the light and z its radius, 0 if unused
+float moving_light(vec3 light) {
+    if (light.z <= 0.0) {
+        return 0.0;
+    }
+    return max(0.0, 1.0 - length(v_world_pos - light.xy) / light.z);
+}
+
 void main()
 {
-    gl_FragColor = v_color;
+    vec2 uv = (v_world_pos - u_light_map_pos) / u_light_map_size;
+    float local_light = 0.0;
+    if (uv.x >= 0.0 && uv.x <= 1.0 && uv.y >= 0.0 && uv.y <= 1.0) {
+        local_light = texture2D(u_light_map, uv).x;
+    }
+    local_light += moving_light(u_moving_light0) + moving_light(u_moving_light1)
+        + moving_light(u_moving_light2) + moving_light(u_moving_light3);
+    local_light = min(local_light, 1.0);
+    vec3 light = v_light + local_light * LIGHT_COLOR;
+    gl_FragColor = vec4(v_color.xyz * min(light + vec3(v_emission), vec3(1.0)), v_color.w);
 }
 #endif
\ No newline at end of file
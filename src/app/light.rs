@@ -1,15 +1,136 @@
 use super::*;
 
+// Light map texels per world unit
+const LIGHT_MAP_RESOLUTION: f32 = 2.0;
+const MAX_LIGHT_MAP_SIZE: usize = 256;
+
+// Moving lights are passed to the shader one by one, x and y are the position and z the radius.
+// Lights past MAX_MOVING_LIGHTS are not drawn, a radius of 0 marks an unused slot.
+const MAX_MOVING_LIGHTS: usize = 4;
+
 #[derive(ugli::Uniforms)]
 pub struct Uniforms {
     pub u_light_direction: Vec3<f32>,
+    pub u_daylight: f32,
+    pub u_light_map: ugli::Texture,
+    pub u_light_map_pos: Vec2<f32>,
+    pub u_light_map_size: Vec2<f32>,
+    pub u_moving_light0: Vec3<f32>,
+    pub u_moving_light1: Vec3<f32>,
+    pub u_moving_light2: Vec3<f32>,
+    pub u_moving_light3: Vec3<f32>,
 }
 
 impl Uniforms {
-    pub fn new(_view: &model::ClientView) -> Self {
-        let angle = f32::PI / 3.0;
-        Self {
-            u_light_direction: vec3(angle.cos(), 0.0, angle.sin()) * angle.sin(),
+    pub fn new(geng: &Geng, view: &model::ClientView) -> Self {
+        let (light_map, light_map_pos, light_map_size) =
+            Self::light_map(geng, &static_lights(&view.lights));
+        let mut uniforms = Self {
+            u_light_direction: vec3(0.0, 0.0, 0.0),
+            u_daylight: 0.0,
+            u_light_map: light_map,
+            u_light_map_pos: light_map_pos,
+            u_light_map_size: light_map_size,
+            u_moving_light0: vec3(0.0, 0.0, 0.0),
+            u_moving_light1: vec3(0.0, 0.0, 0.0),
+            u_moving_light2: vec3(0.0, 0.0, 0.0),
+            u_moving_light3: vec3(0.0, 0.0, 0.0),
+        };
+        uniforms.set_time_of_day(view.time_of_day);
+        uniforms.set_moving_lights(&view.lights);
+        uniforms
+    }
+
+    // The light map is only rebuilt when the static lights differ from the previous view
+    pub fn update(
+        &mut self,
+        geng: &Geng,
+        view: &model::ClientView,
+        previous_lights: &[model::ClientLight],
+    ) {
+        self.set_time_of_day(view.time_of_day);
+        self.set_moving_lights(&view.lights);
+        let static_lights = static_lights(&view.lights);
+        if static_lights != self::static_lights(previous_lights) {
+            let (light_map, light_map_pos, light_map_size) = Self::light_map(geng, &static_lights);
+            self.u_light_map = light_map;
+            self.u_light_map_pos = light_map_pos;
+            self.u_light_map_size = light_map_size;
+        }
+    }
+
+    fn set_moving_lights(&mut self, lights: &[model::ClientLight]) {
+        let mut moving_lights = lights
+            .iter()
+            .filter(|light| light.moving)
+            .map(|light| vec3(light.pos.x, light.pos.y, light.radius))
+            .chain(std::iter::repeat(vec3(0.0, 0.0, 0.0)))
+            .take(MAX_MOVING_LIGHTS);
+        for uniform in [
+            &mut self.u_moving_light0,
+            &mut self.u_moving_light1,
+            &mut self.u_moving_light2,
+            &mut self.u_moving_light3,
+        ] {
+            *uniform = moving_lights.next().unwrap();
         }
     }
+
+    fn set_time_of_day(&mut self, time_of_day: f32) {
+        // The sun rises at a quarter of the day and sets at three quarters
+        let angle = (time_of_day - 0.25) * 2.0 * f32::PI;
+        self.u_light_direction = vec3(angle.cos(), 0.0, angle.sin()) * angle.sin().max(0.0);
+        self.u_daylight = 0.25 + 0.75 * (angle.sin() + 0.3).clamp(0.0, 1.0);
+    }
+
+    fn light_map(
+        geng: &Geng,
+        lights: &[model::ClientLight],
+    ) -> (ugli::Texture, Vec2<f32>, Vec2<f32>) {
+        let mut lights_iter = lights.iter();
+        let first = match lights_iter.next() {
+            Some(light) => light,
+            None => {
+                let texture = ugli::Texture::new_with(geng.ugli(), vec2(1, 1), |_| Color::BLACK);
+                return (texture, vec2(0.0, 0.0), vec2(1.0, 1.0));
+            }
+        };
+        let mut min = first.pos - vec2(first.radius, first.radius);
+        let mut max = first.pos + vec2(first.radius, first.radius);
+        for light in lights_iter {
+            min = vec2(
+                min.x.min(light.pos.x - light.radius),
+                min.y.min(light.pos.y - light.radius),
+            );
+            max = vec2(
+                max.x.max(light.pos.x + light.radius),
+                max.y.max(light.pos.y + light.radius),
+            );
+        }
+        let size = max - min;
+        let texture_size =
+            size.map(|x| ((x * LIGHT_MAP_RESOLUTION).ceil() as usize).clamp(1, MAX_LIGHT_MAP_SIZE));
+        let texture = ugli::Texture::new_with(geng.ugli(), texture_size, |pos| {
+            let pos = min
+                + vec2(
+                    (pos.x as f32 + 0.5) / texture_size.x as f32 * size.x,
+                    (pos.y as f32 + 0.5) / texture_size.y as f32 * size.y,
+                );
+            let intensity: f32 = lights
+                .iter()
+                .map(|light| (1.0 - (pos - light.pos).len() / light.radius).max(0.0))
+                .sum();
+            let intensity = intensity.min(1.0);
+            Color::rgb(intensity, intensity, intensity)
+        });
+        (texture, min, size)
+    }
+}
+
+fn static_lights(lights: &[model::ClientLight]) -> Vec<model::ClientLight> {
+    lights
+        .iter()
+        .filter(|light| !light.moving)
+        .cloned()
+        .collect()
 }
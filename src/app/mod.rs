@@ -11,8 +11,9 @@ mod traffic;
 
 use camera::Camera;
 use client_entity::ClientEntity;
+pub use client_entity::ClientEntityComponents;
 use ez3d::Ez3D;
-pub use resource_pack::ResourcePack;
+pub use resource_pack::{BiomeRendering, EntityInfo, ResourcePack};
 use tile_mesh::TileMesh;
 
 #[derive(geng::Assets)]
@@ -48,6 +49,25 @@ impl Default for Settings {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct PlayerToken(pub String);
+
+impl Default for PlayerToken {
+    fn default() -> Self {
+        Self(format!(
+            "{:016x}{:016x}",
+            global_rng().gen::<u64>(),
+            global_rng().gen::<u64>()
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct PackDataCache {
+    pub hash: Option<u64>,
+    pub data: model::PackData,
+}
+
 struct UiState {
     geng: Geng,
     settings: AutoSave<Settings>,
@@ -94,6 +114,7 @@ pub struct App {
     traffic_counter: traffic::Counter,
     geng: Geng,
     resource_pack: Rc<ResourcePack>,
+    pack_data: model::PackData,
     assets: Assets,
     framebuffer_size: Vec2<usize>,
     camera: Camera,
@@ -117,18 +138,20 @@ impl App {
         geng: &Geng,
         assets: Assets,
         resource_pack: &Rc<ResourcePack>,
+        pack_data: model::PackData,
         player_id: Id,
         view: model::ClientView,
         mut connection: Connection,
     ) -> Self {
         let ez3d = Rc::new(Ez3D::new(geng));
         let ez3d = &ez3d;
-        let light = light::Uniforms::new(&view);
+        let light = light::Uniforms::new(geng, &view);
         let tile_mesh = TileMesh::new(geng, ez3d, resource_pack);
         connection.send(ClientMessage::RequestUpdate { load_area: None });
         Self {
             geng: geng.clone(),
             resource_pack: resource_pack.clone(),
+            pack_data,
             assets,
             traffic_counter: traffic::Counter::new(),
             framebuffer_size: vec2(1, 1),
@@ -165,6 +188,13 @@ impl App {
         }
     }
 
+    fn get_closest_entity(&self, pos: Vec2<f32>) -> Option<&ClientEntity> {
+        self.entities
+            .values()
+            .filter(|entity| entity.pos.is_some() && entity.size.is_some())
+            .find(|entity| (entity.pos.unwrap() - pos).len() <= entity.size.unwrap())
+    }
+
     fn draw_circle(
         &self,
         framebuffer: &mut ugli::Framebuffer,
@@ -236,51 +266,62 @@ impl geng::State for App {
                         sound.set_volume(self.ui_state.volume());
                         sound.play();
                     }
+                    self.light.update(&self.geng, &view, &self.view.lights);
                     self.view = view;
                 }
+                ServerMessage::EntitySpawned(entity) => {
+                    self.entities
+                        .insert(entity.id, ClientEntity::new(entity, &self.resource_pack));
+                }
+                ServerMessage::EntityChanged(entity) => {
+                    if let Some(client_entity) = self.entities.get_mut(&entity.id) {
+                        client_entity.update_client(entity);
+                    }
+                }
+                ServerMessage::EntityRemoved(id) => {
+                    self.entities.remove(&id);
+                }
                 ServerMessage::UpdateTiles(tiles) => {
                     self.tile_mesh.update(&tiles);
                 }
                 ServerMessage::UnloadArea(area) => {
                     self.tile_mesh.unload(area);
                 }
+                ServerMessage::TickStats(report) => match report {
+                    Some(report) => info!("Server tick stats: {}", report),
+                    None => info!("Server tick stats are not available yet"),
+                },
                 _ => unreachable!(),
             }
         }
 
-        for server_entity in &self.view.entities {
-            if let Some(entity) = self.entities.get_mut(&server_entity.id) {
-                entity.update_client(server_entity.clone());
-                entity.update(delta_time, self.view.ticks_per_second);
-            } else {
-                self.entities.insert(
-                    server_entity.id,
-                    ClientEntity::new(server_entity.clone(), &self.resource_pack),
-                );
-            }
+        for entity in self.entities.values_mut() {
+            entity.update(delta_time, self.view.ticks_per_second);
         }
 
-        let player_pos = self.entities[&self.player_id].pos.unwrap();
+        let player_pos = self
+            .entities
+            .get(&self.player_id)
+            .map(|entity| entity.pos.unwrap());
 
         if request_update {
             let load_radius = self.camera.distance;
             self.connection.send(ClientMessage::RequestUpdate {
-                load_area: Some(AABB::from_corners(
-                    player_pos - vec2(load_radius, load_radius),
-                    player_pos + vec2(load_radius, load_radius),
-                )),
+                load_area: player_pos.map(|player_pos| {
+                    AABB::from_corners(
+                        player_pos - vec2(load_radius, load_radius),
+                        player_pos + vec2(load_radius, load_radius),
+                    )
+                }),
             });
         }
 
-        self.entities.retain({
-            let view = &self.view;
-            move |&id, _| view.entities.iter().find(|e| e.id == id).is_some()
-        });
-
-        self.camera.center += (player_pos
-            .extend(self.tile_mesh.get_height(player_pos).unwrap_or(0.0))
-            - self.camera.center)
-            * (delta_time * 5.0).min(1.0);
+        if let Some(player_pos) = player_pos {
+            self.camera.center += (player_pos
+                .extend(self.tile_mesh.get_height(player_pos).unwrap_or(0.0))
+                - self.camera.center)
+                * (delta_time * 5.0).min(1.0);
+        }
         self.camera_controls.update(&mut self.camera, delta_time);
     }
     fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
@@ -313,7 +354,7 @@ impl geng::State for App {
                             self.connection.send(ClientMessage::Goto { pos })
                         }
                         geng::MouseButton::Right => {
-                            if let Some(entity) = self.view.get_closest_entity(pos) {
+                            if let Some(entity) = self.get_closest_entity(pos) {
                                 self.connection.send(ClientMessage::Interact {
                                     target: model::ActionTarget {
                                         interaction_type: model::InteractionType::Interact,
@@ -335,7 +376,7 @@ impl geng::State for App {
                         .pixel_ray(self.framebuffer_size, position.map(|x| x as f32)),
                 ) {
                     let pos = pos.xy();
-                    if let Some(entity) = self.view.get_closest_entity(pos) {
+                    if let Some(entity) = self.get_closest_entity(pos) {
                         self.connection.send(ClientMessage::Interact {
                             target: model::ActionTarget {
                                 interaction_type: model::InteractionType::Attack,
@@ -362,18 +403,87 @@ impl geng::State for App {
                         .pixel_ray(self.framebuffer_size, position.map(|x| x as f32)),
                 ) {
                     let pos = pos.xy();
-                    if let Some(entity) = self.view.get_closest_entity(pos) {
+                    if let Some(entity) = self.get_closest_entity(pos) {
                         self.connection
                             .send(ClientMessage::PickUp { id: entity.id });
                     }
                 }
             }
+            geng::Event::KeyDown { key: geng::Key::C } => {
+                let position = self.geng.window().mouse_pos();
+                if let Some(pos) = self.tile_mesh.intersect(
+                    self.camera
+                        .pixel_ray(self.framebuffer_size, position.map(|x| x as f32)),
+                ) {
+                    let pos = pos.xy();
+                    if let Some(entity) = self.get_closest_entity(pos) {
+                        self.connection
+                            .send(ClientMessage::Deposit { id: entity.id });
+                    }
+                }
+            }
+            geng::Event::KeyDown { key: geng::Key::V } => {
+                let position = self.geng.window().mouse_pos();
+                if let Some(pos) = self.tile_mesh.intersect(
+                    self.camera
+                        .pixel_ray(self.framebuffer_size, position.map(|x| x as f32)),
+                ) {
+                    let pos = pos.xy();
+                    // Takes out the last deposited item
+                    let last_item = self.get_closest_entity(pos).and_then(|entity| {
+                        let count = entity.container.as_ref()?.items.len();
+                        Some((entity.id, count.checked_sub(1)?))
+                    });
+                    if let Some((id, index)) = last_item {
+                        self.connection.send(ClientMessage::Withdraw { id, index });
+                    }
+                }
+            }
             geng::Event::KeyDown { key: geng::Key::R } => {
                 self.connection.send(ClientMessage::SayHi)
             }
             geng::Event::KeyDown { key: geng::Key::F } => self.geng.window().toggle_fullscreen(),
+            geng::Event::KeyDown { key: geng::Key::F3 } => {
+                self.connection.send(ClientMessage::RequestTickStats)
+            }
+            geng::Event::KeyDown { key } => {
+                if let Some(slot) = slot_key(key) {
+                    let active_slot = self
+                        .entities
+                        .get(&self.player_id)
+                        .and_then(|player| player.inventory.as_ref())
+                        .map(|inventory| inventory.active_slot);
+                    match active_slot {
+                        Some(active_slot)
+                            if self.geng.window().is_key_pressed(geng::Key::LShift) =>
+                        {
+                            self.connection.send(ClientMessage::SwapSlots {
+                                from: active_slot,
+                                to: slot,
+                            })
+                        }
+                        _ => self.connection.send(ClientMessage::SelectSlot { slot }),
+                    }
+                }
+            }
             _ => {}
         }
         self.camera_controls.handle_event(&mut self.camera, &event);
     }
 }
+
+fn slot_key(key: geng::Key) -> Option<usize> {
+    use geng::Key::*;
+    match key {
+        Num1 => Some(0),
+        Num2 => Some(1),
+        Num3 => Some(2),
+        Num4 => Some(3),
+        Num5 => Some(4),
+        Num6 => Some(5),
+        Num7 => Some(6),
+        Num8 => Some(7),
+        Num9 => Some(8),
+        _ => None,
+    }
+}
@@ -11,8 +11,8 @@ pub struct EntityRendering {
 }
 
 #[derive(Serialize, Deserialize)]
-struct EntityInfo {
-    model: String,
+pub struct EntityInfo {
+    pub model: String,
 }
 
 pub struct ResourcePack {
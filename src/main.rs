@@ -5,8 +5,14 @@ use ::noise::{NoiseFn as _, Seedable as _};
 mod app;
 mod model;
 #[cfg(not(target_arch = "wasm32"))]
+mod preview;
+#[cfg(not(target_arch = "wasm32"))]
 mod server;
+#[cfg(not(target_arch = "wasm32"))]
+mod update_packs;
 pub mod util;
+#[cfg(not(target_arch = "wasm32"))]
+mod validate;
 
 use app::App;
 use model::{Id, Model};
@@ -19,9 +25,16 @@ pub type ClientMessage = model::Message;
 pub enum ServerMessage {
     PlayerId(Id),
     PackList(Vec<String>),
+    PackDataHash(u64),
+    PackData(model::PackData),
     UpdateClientView(model::ClientView),
+    EntitySpawned(model::Entity),
+    EntityChanged(model::Entity),
+    EntityRemoved(Id),
     UpdateTiles(HashMap<Vec2<i64>, model::Tile>),
     UnloadArea(AABB<i64>),
+    // None until the server has run for a full report interval
+    TickStats(Option<model::TickReport>),
 }
 
 type Connection = geng::net::client::Connection<ServerMessage, ClientMessage>;
@@ -36,6 +49,57 @@ struct Opt {
     addr: Option<String>,
     #[clap(long)]
     log_level: Option<log::LevelFilter>,
+    #[clap(long, default_value = "new_world")]
+    world: String,
+    #[clap(long)]
+    seed: Option<u32>,
+    #[clap(long, default_value = "packs")]
+    packs: String,
+    #[clap(long)]
+    config: Option<String>,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clap)]
+enum Command {
+    #[cfg(not(target_arch = "wasm32"))]
+    Preview(preview::PreviewOpt),
+    #[cfg(not(target_arch = "wasm32"))]
+    ValidatePacks(validate::ValidateOpt),
+    #[cfg(not(target_arch = "wasm32"))]
+    UpdatePacks(update_packs::UpdatePacksOpt),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn create_or_load_world(opt: &Opt) -> Model {
+    let mut config: model::Config = match &opt.config {
+        Some(path) => serde_json::from_reader(std::io::BufReader::new(
+            std::fs::File::open(path).expect("Failed to open config file"),
+        ))
+        .expect("Failed to parse config file"),
+        None => default(),
+    };
+    if let Some(seed) = opt.seed {
+        config.seed = seed;
+    }
+    match Model::create(&opt.world, &opt.packs, config) {
+        Ok(model) => {
+            info!("Created world {}", opt.world);
+            model
+        }
+        Err(err) if err.is::<model::WorldExistsError>() => {
+            if opt.seed.is_some() || opt.config.is_some() {
+                warn!(
+                    "World {} already exists, ignoring --seed and --config",
+                    opt.world
+                );
+            }
+            info!("Loading world {}", opt.world);
+            Model::load(&opt.world, &opt.packs).expect("Failed to load world")
+        }
+        Err(err) => panic!("Failed to create world {}: {}", opt.world, err),
+    }
 }
 
 fn main() {
@@ -54,12 +118,29 @@ fn main() {
 
     logger::init_with_level(opt.log_level.unwrap_or(log::LevelFilter::Info)).unwrap();
 
+    if let Some(command) = &opt.command {
+        match command {
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::Preview(preview_opt) => {
+                preview::run(preview_opt).expect("Failed to render preview");
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::ValidatePacks(validate_opt) => {
+                if !validate::run(validate_opt).expect("Failed to validate packs") {
+                    std::process::exit(1);
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::UpdatePacks(update_packs_opt) => {
+                update_packs::run(update_packs_opt).expect("Failed to update packs");
+            }
+        }
+        return;
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     let (server, server_handle) = if !opt.no_server {
-        let server = Server::new(
-            addr,
-            Model::create("new_world").unwrap_or(Model::load("new_world").unwrap()),
-        );
+        let server = Server::new(addr, create_or_load_world(&opt));
         let server_handle = server.handle();
         if std::env::var_os("CARGO_MANIFEST_DIR").is_none() {
             ctrlc::set_handler({
@@ -104,7 +185,12 @@ fn main() {
                         let assets: app::Assets = geng::LoadAsset::load(&geng, ".")
                             .await
                             .expect("Failed to load assets");
-                        let connection = geng::net::client::connect(&addr).await;
+                        let mut connection = geng::net::client::connect(&addr).await;
+                        let player_token: AutoSave<app::PlayerToken> =
+                            AutoSave::load(".player_token");
+                        connection.send(ClientMessage::Login {
+                            token: player_token.0.clone(),
+                        });
                         let (message, connection) = connection.into_future().await;
                         let player_id = match message {
                             Some(ServerMessage::PlayerId(id)) => id,
@@ -118,17 +204,44 @@ fn main() {
                         let resource_pack = app::ResourcePack::load_all(geng.clone(), pack_list)
                             .await
                             .expect("Failed to load resource packs");
-                        let (message, connection) = connection.into_future().await;
-                        let view = match message {
-                            Some(ServerMessage::UpdateClientView(view)) => view,
+                        let (message, mut connection) = connection.into_future().await;
+                        let pack_data_hash = match message {
+                            Some(ServerMessage::PackDataHash(hash)) => hash,
                             _ => unreachable!(),
                         };
+                        let mut pack_data_cache: AutoSave<app::PackDataCache> =
+                            AutoSave::load(".pack_data");
+                        let mut pack_data = if pack_data_cache.hash == Some(pack_data_hash) {
+                            Some(pack_data_cache.data.clone())
+                        } else {
+                            connection.send(ClientMessage::RequestPackData);
+                            None
+                        };
+                        let mut view = None;
+                        while pack_data.is_none() || view.is_none() {
+                            let (message, next_connection) = connection.into_future().await;
+                            connection = next_connection;
+                            match message {
+                                Some(ServerMessage::PackData(data)) => {
+                                    *pack_data_cache = app::PackDataCache {
+                                        hash: Some(pack_data_hash),
+                                        data: data.clone(),
+                                    };
+                                    pack_data = Some(data);
+                                }
+                                Some(ServerMessage::UpdateClientView(new_view)) => {
+                                    view = Some(new_view);
+                                }
+                                _ => unreachable!(),
+                            }
+                        }
                         App::new(
                             &geng,
                             assets,
                             &Rc::new(resource_pack),
+                            pack_data.unwrap(),
                             player_id,
-                            view,
+                            view.unwrap(),
                             connection,
                         )
                     }
@@ -0,0 +1,129 @@
+use super::*;
+use std::collections::BTreeMap;
+
+const MAX_SAMPLES: usize = 4096;
+
+// Result of sampling the world parameter space against generation-biomes.json
+pub struct BiomeCoverage {
+    samples: usize,
+    gaps: usize,
+    gap_example: Option<Vec<(WorldParameter, f32)>>,
+    overlaps: BTreeMap<(Biome, Biome), usize>,
+}
+
+impl BiomeCoverage {
+    pub fn check(resource_pack: &ResourcePack) -> Self {
+        let mut parameters: Vec<(&WorldParameter, &MultiNoiseProperties)> =
+            resource_pack.world_parameters.iter().collect();
+        parameters.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+        let steps = if parameters.is_empty() {
+            1
+        } else {
+            ((MAX_SAMPLES as f64).powf(1.0 / parameters.len() as f64) as usize).max(2)
+        };
+
+        let mut coverage = Self {
+            samples: 0,
+            gaps: 0,
+            gap_example: None,
+            overlaps: BTreeMap::new(),
+        };
+        let mut indices = vec![0; parameters.len()];
+        loop {
+            let sample: Vec<(WorldParameter, f32)> = parameters
+                .iter()
+                .zip(&indices)
+                .map(|((parameter, properties), &index)| {
+                    let t = (index as f32 + 0.5) / steps as f32;
+                    (
+                        (*parameter).clone(),
+                        properties.min_value + (properties.max_value - properties.min_value) * t,
+                    )
+                })
+                .collect();
+            coverage.add_sample(&resource_pack.biome_generation, sample);
+
+            // Advance to the next point of the grid
+            let mut i = 0;
+            while i < indices.len() && indices[i] + 1 == steps {
+                indices[i] = 0;
+                i += 1;
+            }
+            if i == indices.len() {
+                break;
+            }
+            indices[i] += 1;
+        }
+        coverage
+    }
+    fn add_sample(
+        &mut self,
+        biome_generation: &HashMap<Biome, BiomeGeneration>,
+        sample: Vec<(WorldParameter, f32)>,
+    ) {
+        self.samples += 1;
+        let values: HashMap<WorldParameter, f32> = sample.iter().cloned().collect();
+        let mut matching: Vec<&Biome> = biome_generation
+            .iter()
+            .filter(|(_, biome_gen)| {
+                biome_gen.world_parameters.iter().all(|(parameter, zone)| {
+                    values
+                        .get(parameter)
+                        .map_or(false, |&value| zone.0 <= value && value <= zone.1)
+                })
+            })
+            .map(|(biome, _)| biome)
+            .collect();
+        matching.sort();
+        if matching.is_empty() {
+            self.gaps += 1;
+            if self.gap_example.is_none() {
+                self.gap_example = Some(sample);
+            }
+        }
+        for (i, a) in matching.iter().enumerate() {
+            for b in &matching[i + 1..] {
+                *self
+                    .overlaps
+                    .entry(((*a).clone(), (*b).clone()))
+                    .or_default() += 1;
+            }
+        }
+    }
+    pub fn has_gaps(&self) -> bool {
+        self.gaps != 0
+    }
+    pub fn has_overlaps(&self) -> bool {
+        !self.overlaps.is_empty()
+    }
+}
+
+impl Display for BiomeCoverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let percent = |count: usize| count as f32 / self.samples as f32 * 100.0;
+        if self.has_gaps() {
+            write!(
+                f,
+                "{:.1}% of world parameter space matches no biome",
+                percent(self.gaps)
+            )?;
+            if let Some(example) = &self.gap_example {
+                write!(f, ", e.g.")?;
+                for (parameter, value) in example {
+                    write!(f, " {}={:.2}", parameter.0, value)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        for ((a, b), &count) in &self.overlaps {
+            writeln!(
+                f,
+                "Biomes {} and {} overlap in {:.1}%",
+                a,
+                b,
+                percent(count)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -5,6 +5,14 @@ pub struct ChunkedWorld {
     world_gen: WorldGen,
     chunk_size: Vec2<usize>,
     active_chunks: HashMap<Vec2<i64>, Chunk>,
+    entity_chunks: HashMap<Id, Vec2<i64>>,
+    // Seconds the world has been running
+    world_time: f64,
+    time_of_day: f32,
+    unload_times: util::Saved<UnloadTimes>,
+    // Chance for an empty tile to regrow an entity each second
+    regeneration_rate: f64,
+    regenerate_unloaded: bool,
 }
 
 impl ChunkedWorld {
@@ -12,21 +20,39 @@ impl ChunkedWorld {
         path: impl AsRef<std::path::Path>,
         chunk_size: Vec2<usize>,
         world_gen: WorldGen,
+        regeneration_rate: f64,
+        regenerate_unloaded: bool,
     ) -> Self {
         Self {
             path: path.as_ref().to_owned(),
             chunk_size,
             world_gen,
             active_chunks: HashMap::new(),
+            entity_chunks: HashMap::new(),
+            world_time: 0.0,
+            time_of_day: 0.0,
+            unload_times: util::Saved::new(
+                path.as_ref().join("unload_times"),
+                UnloadTimes::default,
+            ),
+            regeneration_rate: regeneration_rate.clamp(0.0, 1.0),
+            regenerate_unloaded,
         }
     }
 
+    // The time of day decides which entities regrow
+    pub fn set_time(&mut self, world_time: f64, time_of_day: f32) {
+        self.world_time = world_time;
+        self.time_of_day = time_of_day;
+    }
+
     pub fn insert_entity(
         &mut self,
         entity: Entity,
         id_generator: &mut IdGenerator,
     ) -> Result<(), Entity> {
         let chunk_pos = self.get_chunk_pos(get_tile_pos(entity.pos.unwrap()));
+        self.entity_chunks.insert(entity.id, chunk_pos);
         let chunk = if let Some(chunk) = self.active_chunks.get_mut(&chunk_pos) {
             chunk
         } else {
@@ -54,39 +80,32 @@ impl ChunkedWorld {
     }
 
     pub fn remove_entity(&mut self, id: Id) -> Option<Entity> {
-        for chunk in self.active_chunks.values_mut() {
-            if let Some(entity) = chunk.borrow_mut().entities.remove(&id) {
-                return Some(entity);
-            }
-        }
-        None
+        let chunk_pos = self.entity_chunks.remove(&id)?;
+        self.active_chunks
+            .get_mut(&chunk_pos)?
+            .borrow_mut()
+            .entities
+            .remove(&id)
     }
 
     pub fn get_entity(&self, id: Id) -> Option<&Entity> {
-        for chunk in self.active_chunks.values() {
-            if let Some(entity) = chunk.entities.get(&id) {
-                return Some(entity);
-            }
-        }
-        None
+        let chunk_pos = self.entity_chunks.get(&id)?;
+        self.active_chunks.get(chunk_pos)?.entities.get(&id)
     }
 
     pub fn get_entity_mut(&mut self, id: Id) -> Option<&mut Entity> {
-        for chunk in self.active_chunks.values_mut() {
-            if let Some(entity) = chunk.borrow_mut().entities.get_mut(&id) {
-                return Some(entity);
-            }
-        }
-        None
+        let chunk_pos = self.entity_chunks.get(&id)?;
+        self.active_chunks
+            .get_mut(chunk_pos)?
+            .borrow_mut()
+            .entities
+            .get_mut(&id)
     }
 
     pub fn get_entity_chunk_pos(&self, id: Id) -> Option<(&Entity, Vec2<i64>)> {
-        for (&chunk_pos, chunk) in &self.active_chunks {
-            if let Some(entity) = chunk.entities.get(&id) {
-                return Some((entity, chunk_pos));
-            }
-        }
-        None
+        let &chunk_pos = self.entity_chunks.get(&id)?;
+        let entity = self.active_chunks.get(&chunk_pos)?.entities.get(&id)?;
+        Some((entity, chunk_pos))
     }
 
     pub fn entities(&self) -> impl Iterator<Item = &Entity> {
@@ -95,10 +114,15 @@ impl ChunkedWorld {
             .flat_map(|chunk| chunk.entities.values())
     }
 
-    pub fn entities_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
-        self.active_chunks
-            .values_mut()
-            .flat_map(|chunk| chunk.borrow_mut().entities.values_mut())
+    // Only visits the chunks covering the area
+    pub fn entities_in_area(&self, area: AABB<f32>) -> impl Iterator<Item = &Entity> {
+        let min = self.get_chunk_pos(get_tile_pos(vec2(area.x_min, area.y_min)));
+        let max = self.get_chunk_pos(get_tile_pos(vec2(area.x_max, area.y_max)));
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| vec2(x, y)))
+            .filter_map(move |chunk_pos| self.active_chunks.get(&chunk_pos))
+            .flat_map(|chunk| chunk.entities.values())
+            .filter(move |entity| entity.pos.map_or(false, |pos| area.contains(pos)))
     }
 
     pub fn find_range(
@@ -111,8 +135,8 @@ impl ChunkedWorld {
         let chunk_dist =
             (vec2(range, range) / self.chunk_size.map(|x| x as f32)).map(|x| x.ceil() as i64);
         let chunk_pos = self.get_chunk_pos(get_tile_pos(pos));
-        for y in chunk_pos.y - chunk_dist.y..chunk_pos.y + chunk_dist.y {
-            for x in chunk_pos.x - chunk_dist.x..chunk_pos.x + chunk_dist.x {
+        for y in chunk_pos.y - chunk_dist.y..=chunk_pos.y + chunk_dist.y {
+            for x in chunk_pos.x - chunk_dist.x..=chunk_pos.x + chunk_dist.x {
                 if let Some(chunk) = self.active_chunks.get(&vec2(x, y)) {
                     for entity in chunk.entities.values().filter(|e| predicate(e)) {
                         let delta = pos - entity.pos.unwrap();
@@ -126,6 +150,36 @@ impl ChunkedWorld {
         entities
     }
 
+    // Regrows resources in the loaded chunks as if the given time has passed
+    pub fn regenerate(&mut self, id_generator: &mut IdGenerator, seconds: f64) {
+        let chunk_positions: Vec<Vec2<i64>> = self.active_chunks.keys().copied().collect();
+        for chunk_pos in chunk_positions {
+            self.regenerate_chunk(chunk_pos, id_generator, seconds);
+        }
+    }
+
+    fn regenerate_chunk(
+        &mut self,
+        chunk_pos: Vec2<i64>,
+        id_generator: &mut IdGenerator,
+        seconds: f64,
+    ) {
+        let chance = 1.0 - (1.0 - self.regeneration_rate).powf(seconds);
+        if chance <= 0.0 {
+            return;
+        }
+        let chunk = self.active_chunks.get_mut(&chunk_pos).unwrap();
+        let entities = chunk.regrow(&self.world_gen, id_generator, chance, self.time_of_day);
+        if entities.is_empty() {
+            return;
+        }
+        let saved_chunk = chunk.borrow_mut();
+        for entity in entities {
+            self.entity_chunks.insert(entity.id, chunk_pos);
+            saved_chunk.entities.insert(entity.id, entity);
+        }
+    }
+
     pub fn get_tile(&self, pos: Vec2<i64>) -> Option<&Tile> {
         let chunk_pos = self.get_chunk_pos(pos);
         self.active_chunks
@@ -136,7 +190,7 @@ impl ChunkedWorld {
     pub fn set_load_area_for(
         &mut self,
         loader: Id,
-        id_generator: &mut IdGenerator,
+        id_generator: &mut util::Saved<IdGenerator>,
         area: Option<AABB<f32>>,
     ) {
         let area = area.map(|area| AABB {
@@ -160,7 +214,67 @@ impl ChunkedWorld {
                 chunk.load(loader);
             }
         }
-        self.active_chunks.retain(|_, chunk| chunk.has_loaders());
+        let unloaded_chunk_positions: Vec<Vec2<i64>> = self
+            .active_chunks
+            .iter()
+            .filter(|(_, chunk)| !chunk.has_loaders())
+            .map(|(&chunk_pos, _)| chunk_pos)
+            .collect();
+        let mut unloaded_chunks = Vec::new();
+        for chunk_pos in unloaded_chunk_positions {
+            let chunk = self.active_chunks.remove(&chunk_pos).unwrap();
+            for id in chunk.entities.keys() {
+                self.entity_chunks.remove(id);
+            }
+            if self.regenerate_unloaded {
+                self.unload_times.chunks.insert(chunk_pos, self.world_time);
+            }
+            if chunk.needs_save() {
+                unloaded_chunks.push((chunk_pos, chunk));
+            }
+        }
+        if unloaded_chunks.is_empty() {
+            return;
+        }
+        // Ids used by the written chunks must not be handed out again after a crash
+        id_generator.autosave();
+        if let Err(e) = util::write_chunks(
+            self.path.join("regions"),
+            unloaded_chunks
+                .iter()
+                .map(|(chunk_pos, chunk)| (*chunk_pos, &*chunk.inner)),
+        ) {
+            error!("Failed to save unloaded chunks: {}", e);
+        }
+    }
+    pub fn autosave(&mut self) {
+        self.unload_times.autosave();
+        let changed_chunks = self
+            .active_chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.needs_save())
+            .map(|(&chunk_pos, chunk)| (chunk_pos, &*chunk.inner));
+        match util::write_chunks(self.path.join("regions"), changed_chunks) {
+            Ok(()) => {
+                for chunk in self.active_chunks.values_mut() {
+                    chunk.changed = false;
+                }
+            }
+            Err(e) => {
+                error!("Failed to save chunks: {}", e);
+                // The other chunks were saved, the unwritable ones are never saved
+                if let Some(e) = e.downcast_ref::<util::UnwritableChunksError>() {
+                    for chunk in self.active_chunks.values_mut() {
+                        chunk.changed = false;
+                    }
+                    for chunk_pos in &e.chunk_positions {
+                        if let Some(chunk) = self.active_chunks.get_mut(chunk_pos) {
+                            chunk.saveable = false;
+                        }
+                    }
+                }
+            }
+        }
     }
     pub fn get_updates(&mut self, loader: Id, sender: &mut dyn geng::net::Sender<ServerMessage>) {
         let mut removes = Vec::new();
@@ -181,17 +295,29 @@ impl ChunkedWorld {
 struct Chunk {
     #[deref]
     #[deref_mut]
-    inner: util::Loaded<util::Saved<SavedChunk>>,
+    inner: util::Loaded<SavedChunk>,
     area: AABB<i64>,
+    changed: bool,
+    // False for chunks that would overwrite data that could not be backed up
+    saveable: bool,
 }
 
 impl Chunk {
-    fn new(area: AABB<i64>, saved: util::Saved<SavedChunk>) -> Self {
+    fn new(area: AABB<i64>, saved: SavedChunk, changed: bool, saveable: bool) -> Self {
         Self {
             inner: util::Loaded::new(saved),
             area,
+            changed,
+            saveable,
         }
     }
+    fn needs_save(&self) -> bool {
+        self.changed && self.saveable
+    }
+    fn borrow_mut(&mut self) -> &mut SavedChunk {
+        self.changed = true;
+        self.inner.borrow_mut()
+    }
     fn get_updates(
         &mut self,
         loader: Id,
@@ -215,6 +341,34 @@ struct SavedChunk {
     entities: HashMap<Id, Entity>,
 }
 
+// World time at which chunks were unloaded, so regrowth can catch up when they are loaded.
+// Chunks that were active at shutdown have no entry, no time passed for them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UnloadTimes {
+    chunks: HashMap<Vec2<i64>, f64>,
+}
+
+impl util::Versioned for UnloadTimes {
+    const VERSION: u32 = 1;
+    fn migrate(version: u32, _data: &[u8]) -> Result<Self, anyhow::Error> {
+        Err(anyhow::Error::from(legacy::UnknownFormatVersionError {
+            version,
+        }))
+    }
+}
+
+impl util::Versioned for SavedChunk {
+    const VERSION: u32 = 1;
+    fn migrate(version: u32, data: &[u8]) -> Result<Self, anyhow::Error> {
+        match version {
+            0 => legacy::v0::load::<legacy::v0::SavedChunk, _>(data),
+            _ => Err(anyhow::Error::from(legacy::UnknownFormatVersionError {
+                version,
+            })),
+        }
+    }
+}
+
 impl SavedChunk {
     fn generate(
         chunk_pos: Vec2<i64>,
@@ -225,7 +379,8 @@ impl SavedChunk {
         let mut tiles = HashMap::new();
         let mut entities = HashMap::new();
         for pos in area.points() {
-            let (tile, entity_type) = world_gen.generate_tile(pos);
+            let tile = world_gen.generate_tile(pos);
+            let entity_type = world_gen.generate_entity(&tile, pos);
             tiles.insert(pos, tile);
             if let Some(entity_type) = entity_type {
                 let entity = Entity::new(
@@ -243,6 +398,160 @@ impl SavedChunk {
             entities,
         }
     }
+    // New entities for empty tiles, tiles next to player built entities are left alone
+    fn regrow(
+        &self,
+        world_gen: &WorldGen,
+        id_generator: &mut IdGenerator,
+        chance: f64,
+        time_of_day: f32,
+    ) -> Vec<Entity> {
+        let occupied: HashSet<Vec2<i64>> = self
+            .entities
+            .values()
+            .map(|entity| get_tile_pos(entity.pos.unwrap()))
+            .collect();
+        let built: Vec<Vec2<i64>> = self
+            .entities
+            .values()
+            .filter(|entity| !world_gen.is_generated(&entity.entity_type))
+            .map(|entity| get_tile_pos(entity.pos.unwrap()))
+            .collect();
+        let mut entities = Vec::new();
+        for (&pos, tile) in &self.tiles {
+            if occupied.contains(&pos)
+                || built
+                    .iter()
+                    .any(|built| (built.x - pos.x).abs() <= 1 && (built.y - pos.y).abs() <= 1)
+                || !global_rng().gen_bool(chance.min(1.0))
+            {
+                continue;
+            }
+            if let Some(entity_type) = world_gen.regenerate_entity(tile, time_of_day) {
+                entities.push(Entity::new(
+                    id_generator.gen(),
+                    &entity_type,
+                    Some(pos.map(|x| x as f32)),
+                    &world_gen.entity_components,
+                ));
+            }
+        }
+        entities
+    }
+}
+
+impl ChunkedWorld {
+    // Moves chunks saved as separate files by older versions into region files
+    pub fn convert_chunk_files(
+        world_path: impl AsRef<std::path::Path>,
+    ) -> Result<(), anyhow::Error> {
+        const BATCH_SIZE: usize = 1024;
+        let chunks_path = world_path.as_ref().join("chunks");
+        let regions_path = world_path.as_ref().join("regions");
+        if !chunks_path.exists() {
+            return Ok(());
+        }
+        let chunk_files = chunk_files(&chunks_path)?;
+        info!(
+            "Converting {} chunk files in {:?} to regions",
+            chunk_files.len(),
+            chunks_path
+        );
+        for batch in chunk_files.chunks(BATCH_SIZE) {
+            let mut saved_chunks = Vec::new();
+            for path in batch {
+                let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+                let saved_chunk: SavedChunk = util::read_versioned(reader)?;
+                saved_chunks.push(saved_chunk);
+            }
+            util::write_chunks(
+                &regions_path,
+                saved_chunks
+                    .iter()
+                    .map(|saved_chunk| (saved_chunk.chunk_pos, saved_chunk)),
+            )?;
+            for path in batch {
+                std::fs::remove_file(path)?;
+            }
+        }
+        if let Err(e) = std::fs::remove_dir(&chunks_path) {
+            warn!("Could not remove {:?}: {}", chunks_path, e);
+        }
+        Ok(())
+    }
+
+    // Visits the chunks saved as separate files by older versions, without changing them
+    pub fn read_chunk_files(
+        world_path: impl AsRef<std::path::Path>,
+        mut visit: impl FnMut(&mut HashMap<Vec2<i64>, Tile>, &mut HashMap<Id, Entity>),
+    ) -> Result<(), anyhow::Error> {
+        let chunks_path = world_path.as_ref().join("chunks");
+        if !chunks_path.exists() {
+            return Ok(());
+        }
+        for path in chunk_files(&chunks_path)? {
+            let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+            let mut saved_chunk: SavedChunk = util::read_versioned(reader)?;
+            visit(&mut saved_chunk.tiles, &mut saved_chunk.entities);
+        }
+        Ok(())
+    }
+}
+
+fn chunk_files(chunks_path: &std::path::Path) -> Result<Vec<std::path::PathBuf>, anyhow::Error> {
+    let mut chunk_files = Vec::new();
+    for entry in std::fs::read_dir(chunks_path)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "chunk")
+        {
+            chunk_files.push(path);
+        }
+    }
+    Ok(chunk_files)
+}
+
+impl ChunkedWorld {
+    // Chunks from a newer version would be lost by regenerating them
+    pub fn check_saved_chunks(
+        world_path: impl AsRef<std::path::Path>,
+    ) -> Result<(), anyhow::Error> {
+        util::check_chunk_versions::<SavedChunk>(world_path.as_ref().join("regions"))
+    }
+}
+
+impl ChunkedWorld {
+    // Runs an update over every saved chunk of a world that is not running,
+    // saving the chunks for which it returns true
+    pub fn update_saved_chunks(
+        world_path: impl AsRef<std::path::Path>,
+        mut update: impl FnMut(&mut HashMap<Vec2<i64>, Tile>, &mut HashMap<Id, Entity>) -> bool,
+    ) -> Result<(), anyhow::Error> {
+        let regions_path = world_path.as_ref().join("regions");
+        let mut changed_chunks = Vec::new();
+        for chunk_pos in util::saved_chunk_positions(&regions_path)? {
+            let mut saved_chunk: SavedChunk = match util::read_chunk(&regions_path, chunk_pos)? {
+                Some(saved_chunk) => saved_chunk,
+                None => continue,
+            };
+            if update(&mut saved_chunk.tiles, &mut saved_chunk.entities) {
+                changed_chunks.push(saved_chunk);
+            }
+        }
+        util::write_chunks(
+            &regions_path,
+            changed_chunks
+                .iter()
+                .map(|saved_chunk| (saved_chunk.chunk_pos, saved_chunk)),
+        )
+    }
+}
+
+impl Drop for ChunkedWorld {
+    fn drop(&mut self) {
+        self.autosave();
+    }
 }
 
 impl ChunkedWorld {
@@ -253,16 +562,81 @@ impl ChunkedWorld {
                 chunk_pos * self.chunk_size.map(|x| x as i64),
                 self.chunk_size.map(|x| x as i64),
             );
-            let chunk_path = self
-                .path
-                .join("chunks")
-                .join(format!("chunk_{}_{}.chunk", chunk_pos.x, chunk_pos.y));
-            let saved_chunk = util::Saved::new(chunk_path, || {
+            // Checked first, since any mutable access marks the table as changed
+            let unloaded_at = if self.unload_times.chunks.contains_key(&chunk_pos) {
+                self.unload_times.chunks.remove(&chunk_pos)
+            } else {
+                None
+            };
+            let mut unloaded_seconds = None;
+            let saved_chunk: Result<Option<SavedChunk>, _> =
+                util::read_chunk(self.path.join("regions"), chunk_pos);
+            let (saved_chunk, changed, saveable) = match saved_chunk {
+                Ok(Some(mut saved_chunk)) => {
+                    if let Some(unloaded_at) = unloaded_at {
+                        unloaded_seconds = Some((self.world_time - unloaded_at).max(0.0));
+                    }
+                    // Players live in their records, one saved in a chunk was online during a crash
+                    let entity_count = saved_chunk.entities.len();
+                    saved_chunk.entities.retain(|_, entity| {
+                        !matches!(entity.controller, Some(CompController::Player { .. }))
+                    });
+                    let removed = entity_count - saved_chunk.entities.len();
+                    if removed != 0 {
+                        warn!(
+                            "Removed {} player entities left in chunk {}",
+                            removed, chunk_pos
+                        );
+                    }
+                    (Some(saved_chunk), removed != 0, true)
+                }
+                Ok(None) => (None, true, true),
+                Err(e) if e.is::<util::NewerFormatError>() => {
+                    // Stands in for the saved chunk, which is left untouched on disk
+                    error!(
+                        "Chunk {} can not be loaded, it will not be saved: {}",
+                        chunk_pos, e
+                    );
+                    (None, false, false)
+                }
+                Err(e) => {
+                    error!("Failed to load chunk {}, regenerating it: {}", chunk_pos, e);
+                    // The regenerated chunk replaces the original only once it is backed up
+                    match util::quarantine_region(
+                        self.path.join("regions"),
+                        self.path.join("quarantine"),
+                        chunk_pos,
+                    ) {
+                        Ok(backup_path) => {
+                            warn!(
+                                "Copied the region of chunk {} to {:?}",
+                                chunk_pos, backup_path
+                            );
+                            (None, true, true)
+                        }
+                        Err(e) => {
+                            error!(
+                                "Failed to back up the region of chunk {}, the chunk will not be saved: {}",
+                                chunk_pos, e
+                            );
+                            (None, false, false)
+                        }
+                    }
+                }
+            };
+            let world_gen = &self.world_gen;
+            let saved_chunk = saved_chunk.unwrap_or_else(|| {
                 info!("Generating chunk {}", chunk_pos);
-                SavedChunk::generate(chunk_pos, &self.world_gen, id_generator, chunk_area)
+                SavedChunk::generate(chunk_pos, world_gen, id_generator, chunk_area)
             });
-            let chunk = Chunk::new(chunk_area, saved_chunk);
+            let chunk = Chunk::new(chunk_area, saved_chunk, changed, saveable);
+            for &id in chunk.entities.keys() {
+                self.entity_chunks.insert(id, chunk_pos);
+            }
             self.active_chunks.insert(chunk_pos, chunk);
+            if let Some(seconds) = unloaded_seconds {
+                self.regenerate_chunk(chunk_pos, id_generator, seconds);
+            }
         }
         self.active_chunks.get_mut(&chunk_pos).unwrap()
     }
@@ -273,3 +647,122 @@ impl ChunkedWorld {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::test_resource_pack;
+
+    #[test]
+    fn player_is_restored_after_crash() {
+        let path = std::env::temp_dir().join(format!("chunked-world-test-{}", std::process::id()));
+        let resource_pack = test_resource_pack();
+        let area = Some(AABB::pos_size(vec2(0.0, 0.0), vec2(1.0, 1.0)));
+        let mut id_generator = util::Saved::new(path.join("id_gen"), IdGenerator::new);
+        let loader = id_generator.gen();
+        let player_type = EntityType("Player".to_owned());
+        let player = Entity::new(
+            id_generator.gen(),
+            &player_type,
+            Some(vec2(0.5, 0.5)),
+            &resource_pack.entity_components,
+        );
+        let player_id = player.id;
+
+        let mut world = ChunkedWorld::new(
+            &path,
+            vec2(4, 4),
+            WorldGen::new(0, None, &resource_pack),
+            0.0,
+            false,
+        );
+        world.set_load_area_for(loader, &mut id_generator, area);
+        world
+            .insert_entity(player.clone(), &mut id_generator)
+            .unwrap();
+        // Crash after an autosave, with the player still online
+        world.autosave();
+        std::mem::forget(world);
+
+        let mut world = ChunkedWorld::new(
+            &path,
+            vec2(4, 4),
+            WorldGen::new(0, None, &resource_pack),
+            0.0,
+            false,
+        );
+        world.set_load_area_for(loader, &mut id_generator, area);
+        let restored = world.get_entity(player_id).is_none();
+        world.insert_entity(player, &mut id_generator).unwrap();
+        let player_count = world
+            .entities()
+            .filter(|entity| entity.id == player_id)
+            .count();
+        drop(world);
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(restored);
+        assert_eq!(player_count, 1);
+    }
+
+    #[test]
+    fn find_range_matches_scan_of_all_entities() {
+        const ENTITY_COUNT: usize = 500;
+        const WORLD_SIZE: f32 = 40.0;
+        let path = std::env::temp_dir().join(format!("find-range-test-{}", std::process::id()));
+        let resource_pack = test_resource_pack();
+        let mut id_generator = util::Saved::new(path.join("id_gen"), IdGenerator::new);
+        let mut world = ChunkedWorld::new(
+            &path,
+            vec2(10, 10),
+            WorldGen::new(0, None, &resource_pack),
+            0.0,
+            false,
+        );
+        let rock = EntityType("Rock".to_owned());
+        for _ in 0..ENTITY_COUNT {
+            let pos = vec2(
+                global_rng().gen_range(0.0..WORLD_SIZE),
+                global_rng().gen_range(0.0..WORLD_SIZE),
+            );
+            let entity = Entity::new(
+                id_generator.gen(),
+                &rock,
+                Some(pos),
+                &resource_pack.entity_components,
+            );
+            world.insert_entity(entity, &mut id_generator).unwrap();
+        }
+
+        let mut mismatches = 0;
+        for range in [0.5, 1.0, 15.0] {
+            for entity in world.entities() {
+                let (id, pos) = (entity.id, entity.pos.unwrap());
+                let mut all: Vec<Id> = world
+                    .entities()
+                    .filter(|other| {
+                        let delta = pos - other.pos.unwrap();
+                        other.id != id
+                            && other.collidable.is_some()
+                            && delta.x * delta.x + delta.y * delta.y <= range * range
+                    })
+                    .map(|other| other.id)
+                    .collect();
+                let mut nearby: Vec<Id> = world
+                    .find_range(pos, range, |other| {
+                        other.id != id && other.collidable.is_some()
+                    })
+                    .into_iter()
+                    .map(|other| other.id)
+                    .collect();
+                all.sort_by_key(|id| id.raw());
+                nearby.sort_by_key(|id| id.raw());
+                if all != nearby {
+                    mismatches += 1;
+                }
+            }
+        }
+        drop(world);
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(mismatches, 0);
+    }
+}
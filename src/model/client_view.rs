@@ -4,25 +4,22 @@ use super::*;
 pub struct ClientView {
     pub players_online: usize,
     pub current_time: usize,
+    pub time_of_day: f32,
     pub ticks_per_second: f32,
-    pub entities: Vec<Entity>,
-    pub recipes: Vec<Recipe>,
     pub sounds: Vec<Sound>,
+    pub lights: Vec<ClientLight>,
 }
 
-impl ClientView {
-    pub fn get_closest_entity(&self, pos: Vec2<f32>) -> Option<&Entity> {
-        self.entities
-            .iter()
-            .filter(|entity| entity.pos.is_some() && entity.size.is_some())
-            .find(|entity| (entity.pos.unwrap() - pos).len() <= entity.size.unwrap())
-    }
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClientLight {
+    pub pos: Vec2<f32>,
+    pub radius: f32,
+    // Lights on entities that can move, drawn without the light map
+    pub moving: bool,
 }
 
 impl Model {
     pub fn get_view(&mut self, player_id: Id) -> ClientView {
-        let entity = self.chunked_world.get_entity(player_id).unwrap();
-
         let vision = ClientView {
             players_online: self
                 .chunked_world
@@ -33,25 +30,90 @@ impl Model {
                 })
                 .count(),
             ticks_per_second: self.ticks_per_second,
-            current_time: self.current_time,
-            entities: self
-                .chunked_world
-                .entities()
-                .filter(|e| {
-                    e.id == entity.id
-                        || e.pos.is_some()
-                            && entity
-                                .load_area
-                                .as_ref()
-                                .unwrap()
-                                .load_area
-                                .contains(e.pos.unwrap())
-                })
-                .cloned()
-                .collect(),
-            recipes: self.resource_pack.recipes.clone(),
+            current_time: self.current_time.ticks,
+            time_of_day: self.time_of_day(),
             sounds: mem::replace(self.sounds.get_mut(&player_id).unwrap(), vec![]),
+            lights: self.get_lights(player_id),
         };
         vision
     }
+    fn get_lights(&self, player_id: Id) -> Vec<ClientLight> {
+        let load_area = self
+            .chunked_world
+            .get_entity(player_id)
+            .unwrap()
+            .load_area
+            .as_ref()
+            .unwrap()
+            .load_area;
+        let mut lights: Vec<ClientLight> = self
+            .chunked_world
+            .entities_in_area(load_area)
+            .filter_map(|e| {
+                // Held items light up their holder
+                let held_light = e
+                    .holding
+                    .as_ref()
+                    .and_then(|holding| holding.entity.as_ref())
+                    .and_then(|held| held.light);
+                let light = e.light.or(held_light)?;
+                Some(ClientLight {
+                    pos: e.pos.unwrap(),
+                    radius: self.rules.light_radius(light),
+                    moving: e.movement_speed.is_some(),
+                })
+            })
+            .collect();
+        // In a stable order, so the client can tell when the lights have changed
+        lights.sort_by_key(|light| (r32(light.pos.x), r32(light.pos.y), r32(light.radius)));
+        lights
+    }
+    pub fn get_entity_updates(
+        &mut self,
+        player_id: Id,
+        sender: &mut dyn geng::net::Sender<ServerMessage>,
+    ) {
+        let load_area = self
+            .chunked_world
+            .get_entity(player_id)
+            .unwrap()
+            .load_area
+            .as_ref()
+            .unwrap()
+            .load_area;
+        let known_entities = self.client_entities.get_mut(&player_id).unwrap();
+        let mut visible = HashSet::new();
+        let player = self.chunked_world.get_entity(player_id);
+        let others = self
+            .chunked_world
+            .entities_in_area(load_area)
+            .filter(|e| e.id != player_id);
+        for entity in player.into_iter().chain(others) {
+            visible.insert(entity.id);
+            let hash = entity_hash(entity);
+            match known_entities.insert(entity.id, hash) {
+                Some(known_hash) if known_hash == hash => {}
+                Some(_) => sender.send(ServerMessage::EntityChanged(entity.clone())),
+                None => sender.send(ServerMessage::EntitySpawned(entity.clone())),
+            }
+        }
+        known_entities.retain(|&id, _| {
+            if visible.contains(&id) {
+                true
+            } else {
+                sender.send(ServerMessage::EntityRemoved(id));
+                false
+            }
+        });
+    }
+}
+
+// Entities hold floats, so they are hashed in serialized form
+fn entity_hash(entity: &Entity) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    bincode::serialize(entity)
+        .expect("Failed to serialize entity")
+        .hash(&mut hasher);
+    hasher.finish()
 }
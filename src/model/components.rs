@@ -1,20 +1,20 @@
 use super::*;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompCollidable {
     pub collision_type: CollisionType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CollisionType {
     Static,
     Pushable,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompPickable();
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CompController {
     Player {
         #[serde(default = "PlayerColors::new")]
@@ -22,16 +22,57 @@ pub enum CompController {
     },
     BiomeRandomWalker {
         biome: Biome,
+        // Rests outside of this time
+        #[serde(default)]
+        active_time: Option<TimeRange>,
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompHolding {
     #[serde(default)]
     pub entity: Option<Box<Entity>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompInventory {
+    pub slots: usize,
+    // The item of the active slot is kept in CompHolding, its entry here stays empty
+    #[serde(default)]
+    pub items: Vec<Option<Box<Entity>>>,
+    #[serde(default)]
+    pub active_slot: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompContainer {
+    pub capacity: usize,
+    // Any entity type is allowed if not set
+    #[serde(default)]
+    pub allowed_types: Option<Vec<EntityType>>,
+    #[serde(default)]
+    pub items: Vec<Box<Entity>>,
+}
+
+impl CompContainer {
+    pub fn accepts(&self, entity_type: &EntityType) -> bool {
+        self.items.len() < self.capacity
+            && self
+                .allowed_types
+                .as_ref()
+                .map_or(true, |allowed_types| allowed_types.contains(entity_type))
+    }
+}
+
+// The radius of each kind of light is set in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LightSource {
+    Campfire,
+    Torch,
+    Statue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompAction {
     #[serde(default)]
     pub current_action: Option<EntityAction>,
@@ -39,12 +80,12 @@ pub struct CompAction {
     pub next_action: Option<EntityAction>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompInteraction {
     pub interaction_range: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompLoadArea {
     #[serde(skip, default = "CompLoadArea::default_load_area")]
     pub load_area: AABB<f32>,
@@ -56,14 +97,23 @@ impl CompLoadArea {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompHP {
     pub max_hp: f32,
     #[serde(default)]
     pub current_hp: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompDurability {
+    pub max_durability: u32,
+    #[serde(default)]
+    pub current_durability: u32,
+    #[serde(default)]
+    pub broken_result: Option<EntityType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompWeapon {
     pub attack_time: f32,
     pub damage: f32,
@@ -1,9 +1,11 @@
 use super::*;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Config {
     pub seed: u32,
     pub ticks_per_second: f32,
+    pub autosave_interval: f32,
     pub chunk_size: Vec2<usize>,
     pub view_distance: f32,
     pub regeneration_percent: f32,
@@ -13,6 +15,10 @@ pub struct Config {
     pub sound_distance: f32,
     pub generation_distance: usize,
     pub spawn_area: f32,
+    pub held_item_on_disconnect: HeldItemPolicy,
+    pub fallback_biome: Option<Biome>,
+    pub day_length: f32,
+    pub regenerate_unloaded_chunks: bool,
 }
 
 impl Default for Config {
@@ -20,6 +26,7 @@ impl Default for Config {
         Self {
             seed: 0,
             ticks_per_second: 20.0,
+            autosave_interval: 60.0,
             chunk_size: vec2(10, 10),
             view_distance: 20.0,
             regeneration_percent: 0.01,
@@ -29,6 +36,22 @@ impl Default for Config {
             sound_distance: 5.0,
             generation_distance: 5,
             spawn_area: 50.0,
+            held_item_on_disconnect: HeldItemPolicy::Drop,
+            fallback_biome: None,
+            day_length: 600.0,
+            regenerate_unloaded_chunks: true,
+        }
+    }
+}
+
+impl util::Versioned for Config {
+    const VERSION: u32 = 1;
+    fn migrate(version: u32, data: &[u8]) -> Result<Self, anyhow::Error> {
+        match version {
+            0 => Ok(serde_json::from_slice(data)?),
+            _ => Err(anyhow::Error::from(legacy::UnknownFormatVersionError {
+                version,
+            })),
         }
     }
 }
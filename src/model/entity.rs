@@ -1,6 +1,6 @@
 use super::*;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Deref, DerefMut)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Deref, DerefMut)]
 pub struct Entity {
     pub entity_type: EntityType,
     pub id: Id,
@@ -9,7 +9,7 @@ pub struct Entity {
     pub components: EntityComponents,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EntityComponents {
     #[serde(default)]
     pub pos: Option<Vec2<f32>>,
@@ -26,6 +26,10 @@ pub struct EntityComponents {
     #[serde(default)]
     pub holding: Option<CompHolding>,
     #[serde(default)]
+    pub inventory: Option<CompInventory>,
+    #[serde(default)]
+    pub container: Option<CompContainer>,
+    #[serde(default)]
     pub interaction: Option<CompInteraction>,
     #[serde(default)]
     pub action: Option<CompAction>,
@@ -35,6 +39,12 @@ pub struct EntityComponents {
     pub hp: Option<CompHP>,
     #[serde(default)]
     pub weapon: Option<CompWeapon>,
+    #[serde(default)]
+    pub loot_table: Option<String>,
+    #[serde(default)]
+    pub durability: Option<CompDurability>,
+    #[serde(default)]
+    pub light: Option<LightSource>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
@@ -58,6 +68,12 @@ impl Entity {
         if let Some(hp) = &mut components.hp {
             hp.current_hp = hp.max_hp;
         }
+        if let Some(durability) = &mut components.durability {
+            durability.current_durability = durability.max_durability;
+        }
+        if let Some(inventory) = &mut components.inventory {
+            inventory.items = vec![None; inventory.slots];
+        }
         Self {
             entity_type: entity_type.clone(),
             components,
@@ -72,9 +88,92 @@ impl Entity {
         let new_pos = entity_pos + dir * movement_speed * delta_time;
         self.pos = Some(new_pos);
     }
+
+    fn slot_mut(&mut self, slot: usize) -> Option<&mut Option<Box<Entity>>> {
+        let inventory = self.components.inventory.as_mut()?;
+        if slot == inventory.active_slot {
+            self.components
+                .holding
+                .as_mut()
+                .map(|holding| &mut holding.entity)
+        } else {
+            inventory.items.get_mut(slot)
+        }
+    }
+
+    pub fn select_slot(&mut self, slot: usize) {
+        if let (Some(inventory), Some(holding)) = (
+            self.components.inventory.as_mut(),
+            self.components.holding.as_mut(),
+        ) {
+            if slot < inventory.items.len() && inventory.active_slot < inventory.items.len() {
+                std::mem::swap(
+                    &mut holding.entity,
+                    &mut inventory.items[inventory.active_slot],
+                );
+                std::mem::swap(&mut holding.entity, &mut inventory.items[slot]);
+                inventory.active_slot = slot;
+            }
+        }
+    }
+
+    pub fn swap_slots(&mut self, from: usize, to: usize) {
+        if from == to || self.slot_mut(from).is_none() || self.slot_mut(to).is_none() {
+            return;
+        }
+        let item = self.slot_mut(from).unwrap().take();
+        let item = std::mem::replace(self.slot_mut(to).unwrap(), item);
+        *self.slot_mut(from).unwrap() = item;
+    }
+
+    // Puts the item into the hand or the first free inventory slot,
+    // returns it back if there is no room
+    pub fn store_item(&mut self, item: Box<Entity>) -> Option<Box<Entity>> {
+        let free_slot = match self.components.holding.as_mut() {
+            Some(holding) if holding.entity.is_none() => Some(&mut holding.entity),
+            Some(_) => self
+                .components
+                .inventory
+                .as_mut()
+                .and_then(|inventory| {
+                    let active_slot = inventory.active_slot;
+                    inventory
+                        .items
+                        .iter_mut()
+                        .enumerate()
+                        .find(|(slot, item)| *slot != active_slot && item.is_none())
+                })
+                .map(|(_, item)| item),
+            None => None,
+        };
+        match free_slot {
+            Some(free_slot) => {
+                *free_slot = Some(item);
+                None
+            }
+            None => Some(item),
+        }
+    }
+
+    // Removes the held item and everything in the inventory
+    pub fn take_items(&mut self) -> Vec<Box<Entity>> {
+        let held = self
+            .components
+            .holding
+            .as_mut()
+            .and_then(|holding| holding.entity.take());
+        let stored = self
+            .components
+            .inventory
+            .as_mut()
+            .into_iter()
+            .flat_map(|inventory| inventory.items.iter_mut())
+            .filter_map(|item| item.take());
+        held.into_iter().chain(stored).collect()
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EntityAction {
     MovingTo {
         target: ActionTarget,
@@ -97,22 +196,29 @@ pub enum EntityAction {
     PickUp {
         id: Id,
     },
+    Deposit {
+        id: Id,
+    },
+    Withdraw {
+        id: Id,
+        index: usize,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ActionTarget {
     pub interaction_type: InteractionType,
     pub target_type: TargetType,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum InteractionType {
     None,
     Interact,
     Attack,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum TargetType {
     Position { pos: Vec2<f32> },
     Entity { id: Id },
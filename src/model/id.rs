@@ -14,6 +14,18 @@ pub struct IdGenerator {
     next_id: usize,
 }
 
+impl util::Versioned for IdGenerator {
+    const VERSION: u32 = 1;
+    fn migrate(version: u32, data: &[u8]) -> Result<Self, anyhow::Error> {
+        match version {
+            0 => Ok(bincode::deserialize(data)?),
+            _ => Err(anyhow::Error::from(legacy::UnknownFormatVersionError {
+                version,
+            })),
+        }
+    }
+}
+
 impl IdGenerator {
     pub fn new() -> Self {
         Self { next_id: 0 }
@@ -0,0 +1,196 @@
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct UnknownFormatVersionError {
+    pub version: u32,
+}
+
+impl std::fmt::Display for UnknownFormatVersionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown save format version {}.", self.version)
+    }
+}
+
+impl std::error::Error for UnknownFormatVersionError {}
+
+// Converts between two layouts of the same data by field names
+pub fn transcode<T: Serialize, U: for<'de> Deserialize<'de>>(
+    value: &T,
+) -> Result<U, anyhow::Error> {
+    Ok(rmp_serde::from_slice(&rmp_serde::to_vec_named(value)?)?)
+}
+
+// Layout of saves before the format was versioned (bincode, no header)
+pub mod v0 {
+    use super::*;
+
+    pub fn load<T: Serialize + for<'de> Deserialize<'de>, U: for<'de> Deserialize<'de>>(
+        data: &[u8],
+    ) -> Result<U, anyhow::Error> {
+        let value: T = bincode::deserialize(data)?;
+        transcode(&value)
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct SavedChunk {
+        pub chunk_pos: Vec2<i64>,
+        pub tiles: HashMap<Vec2<i64>, Tile>,
+        pub entities: HashMap<Id, Entity>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Tile {
+        pub biome: Biome,
+        pub world_parameters: HashMap<WorldParameter, f32>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct PlayerRecords {
+        pub records: HashMap<String, PlayerRecord>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct PlayerRecord {
+        pub id: Id,
+        pub pos: Vec2<f32>,
+        pub colors: PlayerColors,
+        pub hp: Option<f32>,
+        pub holding: Option<Box<Entity>>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct PlayerColors {
+        pub skin: Color<f32>,
+        pub shirt: Color<f32>,
+        pub pants: Color<f32>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Entity {
+        pub entity_type: EntityType,
+        pub id: Id,
+        pub components: EntityComponents,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct EntityComponents {
+        pub pos: Option<Vec2<f32>>,
+        pub size: Option<f32>,
+        pub movement_speed: Option<f32>,
+        pub controller: Option<CompController>,
+        pub collidable: Option<CompCollidable>,
+        pub pickable: Option<CompPickable>,
+        pub holding: Option<CompHolding>,
+        pub interaction: Option<CompInteraction>,
+        pub action: Option<CompAction>,
+        pub load_area: Option<CompLoadArea>,
+        pub hp: Option<CompHP>,
+        pub weapon: Option<CompWeapon>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CompCollidable {
+        pub collision_type: CollisionType,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub enum CollisionType {
+        Static,
+        Pushable,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CompPickable();
+
+    #[derive(Serialize, Deserialize)]
+    pub enum CompController {
+        Player { colors: PlayerColors },
+        BiomeRandomWalker { biome: Biome },
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CompHolding {
+        pub entity: Option<Box<Entity>>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CompAction {
+        pub current_action: Option<EntityAction>,
+        pub next_action: Option<EntityAction>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CompInteraction {
+        pub interaction_range: f32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CompLoadArea {}
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CompHP {
+        pub max_hp: f32,
+        pub current_hp: f32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CompWeapon {
+        pub attack_time: f32,
+        pub damage: f32,
+        pub attack_distance: f32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub enum EntityAction {
+        MovingTo {
+            target: ActionTarget,
+        },
+        Crafting {
+            target: ActionTarget,
+            recipe: Recipe,
+            time_left: f32,
+        },
+        Attacking {
+            target_entity_id: Id,
+            time_left: f32,
+        },
+        Interact {
+            target: ActionTarget,
+        },
+        Drop {
+            pos: Vec2<f32>,
+        },
+        PickUp {
+            id: Id,
+        },
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ActionTarget {
+        pub interaction_type: InteractionType,
+        pub target_type: TargetType,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub enum InteractionType {
+        None,
+        Interact,
+        Attack,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub enum TargetType {
+        Position { pos: Vec2<f32> },
+        Entity { id: Id },
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Recipe {
+        pub ingredient1: Option<EntityType>,
+        pub ingredient2: Option<EntityType>,
+        pub result1: Option<EntityType>,
+        pub result2: Option<EntityType>,
+        pub conditions: Option<Biome>,
+        pub craft_time: f32,
+    }
+}
@@ -0,0 +1,157 @@
+use super::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootTable {
+    pub pools: Vec<LootPool>,
+}
+
+// With the given chance, picks `rolls` weighted entries from the pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootPool {
+    #[serde(default = "LootPool::default_chance")]
+    pub chance: f32,
+    #[serde(default = "LootPool::default_count")]
+    pub rolls: (usize, usize),
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootEntry {
+    pub entity_type: Option<EntityType>,
+    #[serde(default = "LootEntry::default_weight")]
+    pub weight: usize,
+    #[serde(default = "LootEntry::default_count")]
+    pub count: (usize, usize),
+}
+
+impl LootPool {
+    fn default_chance() -> f32 {
+        1.0
+    }
+    fn default_count() -> (usize, usize) {
+        (1, 1)
+    }
+}
+
+impl LootEntry {
+    fn default_weight() -> usize {
+        1
+    }
+    fn default_count() -> (usize, usize) {
+        (1, 1)
+    }
+}
+
+impl LootTable {
+    // Empty ranges would panic when rolled
+    pub fn skip_empty_ranges(&mut self, id: &str) {
+        self.pools.retain(|pool| {
+            if pool.rolls.0 > pool.rolls.1 {
+                warn!(
+                    "Skipping a pool of loot table {} with empty rolls range {:?}",
+                    id, pool.rolls
+                );
+            }
+            pool.rolls.0 <= pool.rolls.1
+        });
+        for pool in &mut self.pools {
+            pool.entries.retain(|entry| {
+                if entry.count.0 > entry.count.1 {
+                    warn!(
+                        "Skipping an entry of loot table {} with empty count range {:?}",
+                        id, entry.count
+                    );
+                }
+                entry.count.0 <= entry.count.1
+            });
+        }
+    }
+    pub fn roll(&self) -> Vec<EntityType> {
+        let mut rng = global_rng();
+        let mut drops = Vec::new();
+        for pool in &self.pools {
+            if rng.gen::<f32>() >= pool.chance {
+                continue;
+            }
+            for _ in 0..rng.gen_range(pool.rolls.0..=pool.rolls.1) {
+                let entry = match pool.entries.choose_weighted(&mut rng, |entry| entry.weight) {
+                    Ok(entry) => entry,
+                    Err(_) => break,
+                };
+                if let Some(entity_type) = &entry.entity_type {
+                    for _ in 0..rng.gen_range(entry.count.0..=entry.count.1) {
+                        drops.push(entity_type.clone());
+                    }
+                }
+            }
+        }
+        drops
+    }
+}
+
+impl Model {
+    pub fn spawn_loot(&mut self, loot_table: &str, pos: Vec2<f32>) {
+        let drops = match self.resource_pack.loot_tables.get(loot_table) {
+            Some(loot_table) => loot_table.roll(),
+            None => {
+                warn!("Unknown loot table {}", loot_table);
+                return;
+            }
+        };
+        let spread = if drops.len() > 1 { 0.5 } else { 0.0 };
+        for entity_type in drops {
+            let mut rng = global_rng();
+            let offset = vec2(
+                rng.gen_range(-spread..=spread),
+                rng.gen_range(-spread..=spread),
+            );
+            self.spawn_entity(&entity_type, pos + offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn killed_entity_drops_its_loot_table() {
+        let entity_components: HashMap<EntityType, EntityComponents> =
+            serde_json::from_value(serde_json::json!({
+                "Deer": { "hp": { "max_hp": 50.0 }, "loot_table": "Deer" }
+            }))
+            .unwrap();
+        let mut loot_tables: HashMap<String, LootTable> =
+            serde_json::from_value(serde_json::json!({
+                "Deer": {
+                    "pools": [
+                        { "entries": [{ "entity_type": "Log" }] },
+                        { "rolls": [2, 2], "entries": [{ "entity_type": "Stick", "count": [3, 3] }] },
+                        { "rolls": [2, 1], "entries": [{ "entity_type": "Rock" }] }
+                    ]
+                }
+            }))
+            .unwrap();
+        for (id, loot_table) in &mut loot_tables {
+            loot_table.skip_empty_ranges(id);
+        }
+        let deer = Entity::new(
+            IdGenerator::new().gen(),
+            &EntityType("Deer".to_owned()),
+            Some(vec2(0.0, 0.0)),
+            &entity_components,
+        );
+
+        // The same lookup kill_entity does for a dead entity
+        let drops = loot_tables[deer.loot_table.as_ref().unwrap()].roll();
+        let count = |name: &str| {
+            drops
+                .iter()
+                .filter(|entity_type| entity_type.0 == name)
+                .count()
+        };
+        assert_eq!(count("Log"), 1);
+        assert_eq!(count("Stick"), 6);
+        assert_eq!(count("Rock"), 0);
+    }
+}
@@ -1,6 +1,7 @@
 use super::*;
 
 mod biome;
+mod biome_coverage;
 mod chunked_world;
 mod client_view;
 mod collision;
@@ -8,17 +9,27 @@ mod components;
 mod config;
 mod entity;
 mod id;
+mod legacy;
+mod loot;
 mod multi_noise;
+mod pack_data;
+mod pack_manifest;
+mod pack_update;
 mod player;
 mod recipe;
 mod resource_pack;
 mod rules;
 mod spawn;
+#[cfg(test)]
+mod test_util;
 mod tick;
+mod tick_report;
 mod tile;
+mod time;
 mod world_gen;
 
 pub use biome::*;
+pub use biome_coverage::*;
 pub use chunked_world::*;
 pub use client_view::*;
 pub use collision::*;
@@ -27,34 +38,57 @@ pub use config::*;
 pub use entity::*;
 use geng::prelude::fmt::Formatter;
 pub use id::*;
+pub use loot::*;
 pub use multi_noise::*;
+pub use pack_data::*;
+pub use pack_manifest::*;
+pub use pack_update::*;
 pub use player::*;
 pub use recipe::*;
 pub use resource_pack::*;
 pub use rules::*;
 pub use tick::*;
+pub use tick_report::*;
 pub use tile::*;
+pub use time::*;
 pub use world_gen::*;
 
 pub struct Model {
     pub ticks_per_second: f32,
+    pub autosave_interval: f32,
+    // Latest tick statistics of the server
+    pub tick_report: Option<TickReport>,
     pub pack_list: Vec<String>,
+    pub pack_data_hash: u64,
+    pack_data: PackData,
     id_generator: util::Saved<IdGenerator>,
+    current_time: util::Saved<WorldTime>,
+    players: util::Saved<PlayerRecords>,
+    player_tokens: HashMap<Id, String>,
     rules: Rules,
     resource_pack: ResourcePack,
     chunked_world: ChunkedWorld,
-    current_time: usize,
     sounds: HashMap<Id, Vec<Sound>>,
+    // Hashes of the entities each client was last sent
+    client_entities: HashMap<Id, HashMap<Id, u64>>,
+    max_entity_size: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum Message {
+    Login { token: String },
     RequestUpdate { load_area: Option<AABB<f32>> },
     Goto { pos: Vec2<f32> },
     Interact { target: ActionTarget },
     Drop { pos: Vec2<f32> },
     PickUp { id: Id },
+    Deposit { id: Id },
+    Withdraw { id: Id, index: usize },
+    SelectSlot { slot: usize },
+    SwapSlots { from: usize, to: usize },
     SayHi,
+    RequestPackData,
+    RequestTickStats,
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone)]
@@ -67,7 +101,7 @@ pub enum Sound {
 }
 
 #[derive(Debug, Clone)]
-struct WorldExistsError {
+pub struct WorldExistsError {
     world_name: String,
 }
 
@@ -84,72 +118,93 @@ impl std::fmt::Display for WorldExistsError {
 impl std::error::Error for WorldExistsError {}
 
 #[derive(Debug, Clone)]
-struct WorldPackConflictError {}
+struct WorldPackConflictError {
+    removed_packs: Vec<String>,
+}
 
 impl std::fmt::Display for WorldPackConflictError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Loaded resource packs do not match loaded world's packs."
+            "Packs {} used by the world are not installed, run update-packs to remove them from the world.",
+            self.removed_packs.join(", ")
         )
     }
 }
 
 impl std::error::Error for WorldPackConflictError {}
 
-impl Model {
-    pub fn create(world_name: &str) -> Result<Self, anyhow::Error> {
-        fn save_to<T: Serialize>(
-            path: impl AsRef<std::path::Path>,
-            value: &T,
-        ) -> Result<(), std::io::Error> {
-            Ok(serde_json::to_writer(
-                std::io::BufWriter::new(std::fs::File::create(path)?),
-                value,
-            )?)
-        }
+#[derive(Debug, Clone)]
+pub struct UndefinedFallbackBiomeError {
+    pub biome: Biome,
+}
 
+impl std::fmt::Display for UndefinedFallbackBiomeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Fallback biome {} is not defined by any pack.",
+            self.biome
+        )
+    }
+}
+
+impl std::error::Error for UndefinedFallbackBiomeError {}
+
+impl Model {
+    pub fn create(
+        world_name: &str,
+        packs_path: impl AsRef<std::path::Path>,
+        config: Config,
+    ) -> Result<Self, anyhow::Error> {
         let world_path = std::path::Path::new("saves").join(world_name);
         if world_path.exists() {
             return Err(anyhow::Error::from(WorldExistsError {
                 world_name: world_name.to_owned(),
             }));
         }
-        let (pack_list, resource_pack) = model::ResourcePack::load_all("packs")?;
-        std::fs::create_dir_all(world_path.join("chunks"))?;
-        save_to(world_path.join("config.json"), &Config::default())?;
-        save_to(world_path.join("pack_list"), &pack_list)?;
-        Ok(Self::new(
-            world_name,
-            Config::default(),
-            pack_list,
-            resource_pack,
-        ))
+        let (pack_list, resource_pack) = model::ResourcePack::load_all(packs_path)?;
+        let model = Self::new(world_name, config.clone(), pack_list, resource_pack)?;
+        std::fs::create_dir_all(world_path.join("regions"))?;
+        util::save_json(world_path.join("config.json"), &config)?;
+        util::save_json(
+            world_path.join("pack_list"),
+            &PackList(model.pack_list.clone()),
+        )?;
+        Ok(model)
     }
-    pub fn load(world_name: &str) -> Result<Self, anyhow::Error> {
-        fn load_from<T: for<'de> Deserialize<'de>>(
-            path: impl AsRef<std::path::Path>,
-        ) -> Result<T, std::io::Error> {
-            Ok(serde_json::from_reader(std::io::BufReader::new(
-                std::fs::File::open(path)?,
-            ))?)
-        }
-
-        let (pack_list, resource_pack) = model::ResourcePack::load_all("packs")?;
+    pub fn load(
+        world_name: &str,
+        packs_path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, anyhow::Error> {
+        let (pack_list, resource_pack) = model::ResourcePack::load_all(packs_path)?;
         let world_path = std::path::Path::new("saves").join(world_name);
-        let world_pack_list: Vec<String> = load_from(world_path.join("pack_list"))?;
-        if world_pack_list != pack_list {
-            return Err(anyhow::Error::from(WorldPackConflictError {}));
+        let PackList(world_pack_list) = util::load_json(world_path.join("pack_list"))?;
+        let removed_packs: Vec<String> = world_pack_list
+            .iter()
+            .filter(|pack| !pack_list.contains(pack))
+            .cloned()
+            .collect();
+        if !removed_packs.is_empty() {
+            return Err(anyhow::Error::from(WorldPackConflictError {
+                removed_packs,
+            }));
+        }
+        if pack_list.iter().any(|pack| !world_pack_list.contains(pack)) {
+            info!("Adding new packs to world {}", world_name);
+            util::save_json(world_path.join("pack_list"), &PackList(pack_list.clone()))?;
         }
-        let config: Config = load_from(world_path.join("config.json"))?;
-        Ok(Self::new(world_name, config, pack_list, resource_pack))
+        let config: Config = util::load_json(world_path.join("config.json"))?;
+        ChunkedWorld::convert_chunk_files(&world_path)?;
+        ChunkedWorld::check_saved_chunks(&world_path)?;
+        Self::new(world_name, config, pack_list, resource_pack)
     }
     fn new(
         world_name: &str,
         config: Config,
         pack_list: Vec<String>,
         resource_pack: ResourcePack,
-    ) -> Self {
+    ) -> Result<Self, anyhow::Error> {
         let world_path = std::path::Path::new("saves").join(world_name);
         let rules = Rules {
             client_view_distance: config.view_distance,
@@ -157,25 +212,113 @@ impl Model {
             torch_light: config.torch_light,
             statue_light: config.statue_light,
             regeneration_percent: config.regeneration_percent,
+            day_length: config.day_length,
+            regenerate_unloaded_chunks: config.regenerate_unloaded_chunks,
             sound_distance: config.sound_distance,
             generation_distance: config.generation_distance,
             spawn_area: config.spawn_area,
+            held_item_on_disconnect: config.held_item_on_disconnect,
         };
-        let world_gen = WorldGen::new(config.seed, &resource_pack);
-        Self {
+        let biome_coverage = BiomeCoverage::check(&resource_pack);
+        if biome_coverage.has_gaps() || biome_coverage.has_overlaps() {
+            warn!("Biome generation is not exact:\n{}", biome_coverage);
+        }
+        match &config.fallback_biome {
+            Some(biome) if !resource_pack.biome_properties.contains_key(biome) => {
+                return Err(anyhow::Error::from(UndefinedFallbackBiomeError {
+                    biome: biome.clone(),
+                }));
+            }
+            None if biome_coverage.has_gaps() => {
+                warn!("No fallback_biome configured, the nearest biome will be used for gaps");
+            }
+            _ => {}
+        }
+        let world_gen = WorldGen::new(config.seed, config.fallback_biome.clone(), &resource_pack);
+        let max_entity_size = resource_pack
+            .entity_components
+            .values()
+            .filter_map(|components| components.size)
+            .fold(0.0, f32::max);
+        let pack_data = PackData::new(&resource_pack);
+        let chunked_world = ChunkedWorld::new(
+            &world_path,
+            config.chunk_size,
+            world_gen,
+            rules.regeneration_percent as f64 / 100.0,
+            rules.regenerate_unloaded_chunks,
+        );
+        let mut model = Self {
+            pack_data_hash: pack_data.hash(),
+            pack_data,
             id_generator: util::Saved::new(world_path.join("id_gen"), IdGenerator::new),
+            current_time: util::Saved::new(world_path.join("time"), WorldTime::default),
+            players: util::Saved::new(world_path.join("players"), PlayerRecords::default),
+            player_tokens: HashMap::new(),
             pack_list,
             rules,
             resource_pack,
             ticks_per_second: config.ticks_per_second,
-            chunked_world: ChunkedWorld::new(world_path, config.chunk_size, world_gen),
-            current_time: 0,
+            autosave_interval: config.autosave_interval,
+            tick_report: None,
+            chunked_world,
             sounds: HashMap::new(),
+            client_entities: HashMap::new(),
+            max_entity_size,
+        };
+        let (world_time, time_of_day) = (model.world_time(), model.time_of_day());
+        model.chunked_world.set_time(world_time, time_of_day);
+        Ok(model)
+    }
+    pub fn autosave(&mut self) {
+        for (&player_id, token) in &self.player_tokens {
+            if let Some(entity) = self.chunked_world.get_entity(player_id) {
+                self.players
+                    .records
+                    .insert(token.clone(), PlayerRecord::new(entity));
+            }
         }
+        self.players.autosave();
+        self.id_generator.autosave();
+        self.current_time.autosave();
+        self.chunked_world.autosave();
     }
     pub fn drop_player(&mut self, player_id: Id) {
-        self.chunked_world.remove_entity(player_id);
+        if let Some(mut entity) = self.chunked_world.remove_entity(player_id) {
+            let policy = match self.rules.held_item_on_disconnect {
+                // Kept items live in the player record, players without a token have none
+                HeldItemPolicy::Keep if !self.player_tokens.contains_key(&player_id) => {
+                    HeldItemPolicy::Drop
+                }
+                policy => policy,
+            };
+            match policy {
+                HeldItemPolicy::Drop => {
+                    let items = entity.take_items();
+                    if !items.is_empty() {
+                        let pos = entity.pos.unwrap();
+                        for mut item in items {
+                            item.pos = Some(pos);
+                            self.chunked_world
+                                .insert_entity(*item, &mut self.id_generator)
+                                .unwrap();
+                        }
+                        self.play_sound(Sound::PutDown, pos);
+                    }
+                }
+                HeldItemPolicy::Keep => {}
+                HeldItemPolicy::Destroy => {
+                    entity.take_items();
+                }
+            }
+            if let Some(token) = self.player_tokens.remove(&player_id) {
+                self.players
+                    .records
+                    .insert(token, PlayerRecord::new(&entity));
+            }
+        }
         self.sounds.remove(&player_id);
+        self.client_entities.remove(&player_id);
         self.chunked_world
             .set_load_area_for(player_id, &mut self.id_generator, None);
     }
@@ -191,6 +334,9 @@ impl Model {
             .unwrap()
             .clone();
         match message {
+            Message::Login { .. } => {
+                warn!("Player {:?} tried to log in twice", player_id);
+            }
             Message::RequestUpdate { load_area } => {
                 if let Some(load_area) = load_area {
                     entity.load_area.as_mut().unwrap().load_area = load_area;
@@ -199,8 +345,9 @@ impl Model {
                         &mut self.id_generator,
                         Some(load_area),
                     );
+                    *self.chunked_world.get_entity_mut(player_id).unwrap() = entity.clone();
                 }
-                // TODO: Diff?
+                self.get_entity_updates(player_id, sender);
                 sender.send(ServerMessage::UpdateClientView(self.get_view(player_id)));
                 self.chunked_world.get_updates(player_id, sender);
             }
@@ -222,11 +369,26 @@ impl Model {
             Message::PickUp { id } => {
                 entity.action.as_mut().unwrap().current_action = Some(EntityAction::PickUp { id });
             }
+            Message::Deposit { id } => {
+                entity.action.as_mut().unwrap().current_action = Some(EntityAction::Deposit { id });
+            }
+            Message::Withdraw { id, index } => {
+                entity.action.as_mut().unwrap().current_action =
+                    Some(EntityAction::Withdraw { id, index });
+            }
+            Message::SelectSlot { slot } => entity.select_slot(slot),
+            Message::SwapSlots { from, to } => entity.swap_slots(from, to),
             Message::SayHi => {
                 if let Some(pos) = entity.pos {
                     self.play_sound(Sound::Hello, pos);
                 }
             }
+            Message::RequestPackData => {
+                sender.send(ServerMessage::PackData(self.pack_data.clone()));
+            }
+            Message::RequestTickStats => {
+                sender.send(ServerMessage::TickStats(self.tick_report.clone()));
+            }
         }
         *self.chunked_world.get_entity_mut(player_id).unwrap() = entity;
     }
@@ -0,0 +1,20 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PackData {
+    pub recipes: Vec<Recipe>,
+}
+
+impl PackData {
+    pub fn new(resource_pack: &ResourcePack) -> Self {
+        Self {
+            recipes: resource_pack.recipes.clone(),
+        }
+    }
+    pub fn hash(&self) -> u64 {
+        use std::hash::Hasher;
+        let mut hasher = siphasher::sip::SipHasher::new();
+        hasher.write(&bincode::serialize(self).unwrap());
+        hasher.finish()
+    }
+}
@@ -0,0 +1,133 @@
+use super::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub id: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default)]
+    pub load_after: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum PackOrderError {
+    IdMismatch { dir_name: String, id: String },
+    MissingDependency { pack: String, dependency: String },
+    Cycle { packs: Vec<String> },
+}
+
+impl std::fmt::Display for PackOrderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IdMismatch { dir_name, id } => write!(
+                f,
+                "Pack in directory {} has id {}, pack ids must match their directory names.",
+                dir_name, id
+            ),
+            Self::MissingDependency { pack, dependency } => write!(
+                f,
+                "Pack {} depends on {}, which is not installed.",
+                pack, dependency
+            ),
+            Self::Cycle { packs } => {
+                write!(f, "Packs {} have circular dependencies.", packs.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PackOrderError {}
+
+impl PackManifest {
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        let dir_name = path.file_name().unwrap().to_str().unwrap().to_owned();
+        let manifest_path = path.join("pack.json");
+        if !manifest_path.exists() {
+            warn!("Pack {} has no pack.json", dir_name);
+            return Ok(Self {
+                id: dir_name,
+                version: String::new(),
+                dependencies: Vec::new(),
+                load_after: Vec::new(),
+            });
+        }
+        let reader = std::io::BufReader::new(std::fs::File::open(manifest_path)?);
+        let manifest: Self = serde_json::from_reader(reader)?;
+        if manifest.id != dir_name {
+            return Err(anyhow::Error::from(PackOrderError::IdMismatch {
+                dir_name,
+                id: manifest.id,
+            }));
+        }
+        Ok(manifest)
+    }
+
+    // Dependencies and load_after entries go first, otherwise packs are ordered by id
+    pub fn load_order(
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Vec<(Self, std::path::PathBuf)>, anyhow::Error> {
+        let mut packs = HashMap::new();
+        for pack in std::fs::read_dir(path.as_ref())? {
+            let pack_path = pack?.path();
+            if pack_path.is_dir() {
+                let manifest = Self::load(&pack_path)?;
+                packs.insert(manifest.id.clone(), (manifest, pack_path));
+            }
+        }
+
+        let mut remaining_before: HashMap<&str, usize> = HashMap::new();
+        let mut after: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (id, (manifest, _)) in &packs {
+            let mut before = 0;
+            for dependency in &manifest.dependencies {
+                if !packs.contains_key(dependency) {
+                    return Err(anyhow::Error::from(PackOrderError::MissingDependency {
+                        pack: id.clone(),
+                        dependency: dependency.clone(),
+                    }));
+                }
+            }
+            for other in manifest.dependencies.iter().chain(&manifest.load_after) {
+                if packs.contains_key(other) {
+                    before += 1;
+                    after.entry(other.as_str()).or_default().push(id.as_str());
+                }
+            }
+            remaining_before.insert(id.as_str(), before);
+        }
+
+        let mut ready: std::collections::BTreeSet<&str> = remaining_before
+            .iter()
+            .filter(|(_, &before)| before == 0)
+            .map(|(&id, _)| id)
+            .collect();
+        let mut order = Vec::new();
+        while let Some(id) = ready.iter().next().copied() {
+            ready.remove(id);
+            order.push(id.to_owned());
+            for &next in after.get(id).into_iter().flatten() {
+                let before = remaining_before.get_mut(next).unwrap();
+                *before -= 1;
+                if *before == 0 {
+                    ready.insert(next);
+                }
+            }
+        }
+        if order.len() != packs.len() {
+            let mut cycle: Vec<String> = packs
+                .keys()
+                .filter(|id| !order.contains(id))
+                .cloned()
+                .collect();
+            cycle.sort();
+            return Err(anyhow::Error::from(PackOrderError::Cycle { packs: cycle }));
+        }
+        Ok(order
+            .into_iter()
+            .map(|id| packs.remove(&id).unwrap())
+            .collect())
+    }
+}
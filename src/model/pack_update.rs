@@ -0,0 +1,236 @@
+use super::*;
+
+pub enum OrphanPolicy {
+    Report,
+    Delete,
+    Replace(EntityType),
+}
+
+#[derive(Debug, Clone)]
+struct UnknownReplacementError {
+    entity_type: EntityType,
+}
+
+impl std::fmt::Display for UnknownReplacementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Replacement entity type {} is not defined by the remaining packs.",
+            self.entity_type
+        )
+    }
+}
+
+impl std::error::Error for UnknownReplacementError {}
+
+#[derive(Default)]
+struct OrphanReport {
+    entities: HashMap<EntityType, usize>,
+    tiles: HashMap<Biome, usize>,
+}
+
+struct OrphanFixer<'a> {
+    resource_pack: &'a ResourcePack,
+    world_gen: &'a WorldGen,
+    policy: &'a OrphanPolicy,
+    report: OrphanReport,
+}
+
+impl OrphanFixer<'_> {
+    fn is_orphan(&self, entity: &Entity) -> bool {
+        !self
+            .resource_pack
+            .entity_components
+            .contains_key(&entity.entity_type)
+    }
+    fn fix_item(&mut self, item: &mut Option<Box<Entity>>) -> bool {
+        let mut entity = item.take().map(|entity| *entity);
+        let changed = self.fix_entity(&mut entity);
+        *item = entity.map(Box::new);
+        changed
+    }
+    // Returns whether the entity was changed, the entity is removed if it becomes None
+    fn fix_entity(&mut self, entity: &mut Option<Entity>) -> bool {
+        let mut changed = false;
+        if let Some(held) = entity.as_mut().and_then(|entity| entity.holding.as_mut()) {
+            changed |= self.fix_item(&mut held.entity);
+        }
+        if let Some(inventory) = entity.as_mut().and_then(|entity| entity.inventory.as_mut()) {
+            for item in &mut inventory.items {
+                changed |= self.fix_item(item);
+            }
+        }
+        if let Some(container) = entity.as_mut().and_then(|entity| entity.container.as_mut()) {
+            for item in std::mem::take(&mut container.items) {
+                let mut item = Some(item);
+                changed |= self.fix_item(&mut item);
+                container.items.extend(item);
+            }
+        }
+        match entity {
+            Some(orphan) if self.is_orphan(orphan) => {
+                *self
+                    .report
+                    .entities
+                    .entry(orphan.entity_type.clone())
+                    .or_default() += 1;
+                match self.policy {
+                    OrphanPolicy::Report => {}
+                    OrphanPolicy::Delete => *entity = None,
+                    OrphanPolicy::Replace(entity_type) => {
+                        *orphan = Entity::new(
+                            orphan.id,
+                            entity_type,
+                            orphan.pos,
+                            &self.resource_pack.entity_components,
+                        );
+                    }
+                }
+                true
+            }
+            _ => changed,
+        }
+    }
+    fn fix_chunk(
+        &mut self,
+        tiles: &mut HashMap<Vec2<i64>, Tile>,
+        entities: &mut HashMap<Id, Entity>,
+    ) -> bool {
+        let mut changed = false;
+        for (&pos, tile) in tiles.iter_mut() {
+            if !self
+                .resource_pack
+                .biome_properties
+                .contains_key(&tile.biome)
+            {
+                *self.report.tiles.entry(tile.biome.clone()).or_default() += 1;
+                if !matches!(self.policy, OrphanPolicy::Report) {
+                    *tile = self.world_gen.generate_tile(pos);
+                }
+                changed = true;
+            }
+        }
+        let ids: Vec<Id> = entities.keys().copied().collect();
+        for id in ids {
+            let mut entity = entities.remove(&id);
+            changed |= self.fix_entity(&mut entity);
+            if let Some(entity) = entity {
+                entities.insert(id, entity);
+            }
+        }
+        changed && !matches!(self.policy, OrphanPolicy::Report)
+    }
+}
+
+impl Model {
+    // Makes an existing world use the currently installed packs.
+    // Added packs only affect chunks generated from now on; content of removed
+    // packs is reported, and deleted or replaced depending on the policy.
+    pub fn update_packs(
+        world_name: &str,
+        packs_path: impl AsRef<std::path::Path>,
+        policy: OrphanPolicy,
+    ) -> Result<(), anyhow::Error> {
+        let world_path = std::path::Path::new("saves").join(world_name);
+        let (pack_list, resource_pack) = ResourcePack::load_all(packs_path)?;
+        let PackList(world_pack_list) = util::load_json(world_path.join("pack_list"))?;
+        let config: Config = util::load_json(world_path.join("config.json"))?;
+        // Report mode reads the chunk files of older versions where they are instead
+        if !matches!(policy, OrphanPolicy::Report) {
+            ChunkedWorld::convert_chunk_files(&world_path)?;
+        }
+        if let OrphanPolicy::Replace(entity_type) = &policy {
+            if !resource_pack.entity_components.contains_key(entity_type) {
+                return Err(anyhow::Error::from(UnknownReplacementError {
+                    entity_type: entity_type.clone(),
+                }));
+            }
+        }
+
+        for pack in pack_list
+            .iter()
+            .filter(|pack| !world_pack_list.contains(pack))
+        {
+            info!("Adding pack {}", pack);
+        }
+        let removed_packs: Vec<&String> = world_pack_list
+            .iter()
+            .filter(|pack| !pack_list.contains(pack))
+            .collect();
+        for pack in &removed_packs {
+            info!("Removing pack {}", pack);
+        }
+
+        let world_gen = WorldGen::new(config.seed, config.fallback_biome.clone(), &resource_pack);
+        let mut fixer = OrphanFixer {
+            resource_pack: &resource_pack,
+            world_gen: &world_gen,
+            policy: &policy,
+            report: default(),
+        };
+        if !removed_packs.is_empty() {
+            ChunkedWorld::update_saved_chunks(&world_path, |tiles, entities| {
+                fixer.fix_chunk(tiles, entities)
+            })?;
+            if matches!(policy, OrphanPolicy::Report) {
+                ChunkedWorld::read_chunk_files(&world_path, |tiles, entities| {
+                    fixer.fix_chunk(tiles, entities);
+                })?;
+            }
+            let players_path = world_path.join("players");
+            if players_path.exists() {
+                // Read without util::Saved, so that report mode never writes the file
+                let reader = std::io::BufReader::new(std::fs::File::open(&players_path)?);
+                let mut players: PlayerRecords = util::read_versioned(reader)?;
+                let mut changed = false;
+                for record in players.records.values_mut() {
+                    changed |= fixer.fix_item(&mut record.holding);
+                    if let Some(inventory) = &mut record.inventory {
+                        for item in &mut inventory.items {
+                            changed |= fixer.fix_item(item);
+                        }
+                    }
+                }
+                if changed && !matches!(policy, OrphanPolicy::Report) {
+                    util::Saved::with_value(players_path, players).save()?;
+                }
+            }
+        }
+
+        let report = fixer.report;
+        for (entity_type, count) in &report.entities {
+            warn!(
+                "{} saved entities of type {} are orphaned",
+                count, entity_type
+            );
+        }
+        for (biome, count) in &report.tiles {
+            warn!("{} saved tiles of biome {} are orphaned", count, biome);
+        }
+        let has_orphans = !report.entities.is_empty() || !report.tiles.is_empty();
+        match policy {
+            OrphanPolicy::Report if has_orphans => {
+                warn!(
+                    "World was not changed, use --delete-orphans or --replace-orphans <entity type> to remove the packs"
+                );
+                return Ok(());
+            }
+            OrphanPolicy::Delete if has_orphans => info!("Deleted orphaned entities"),
+            OrphanPolicy::Replace(entity_type) if has_orphans => {
+                info!("Replaced orphaned entities with {}", entity_type)
+            }
+            _ => {}
+        }
+        if !report.tiles.is_empty() {
+            info!("Regenerated orphaned tiles with the remaining biomes");
+        }
+        let pack_list = PackList(pack_list);
+        util::save_json(world_path.join("pack_list"), &pack_list)?;
+        info!(
+            "World {} now uses packs {}",
+            world_name,
+            pack_list.0.join(", ")
+        );
+        Ok(())
+    }
+}
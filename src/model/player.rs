@@ -52,8 +52,98 @@ impl PlayerColors {
     }
 }
 
+impl PartialEq for PlayerColors {
+    fn eq(&self, other: &Self) -> bool {
+        fn color_eq(a: Color<f32>, b: Color<f32>) -> bool {
+            a.r == b.r && a.g == b.g && a.b == b.b && a.a == b.a
+        }
+        color_eq(self.skin, other.skin)
+            && color_eq(self.shirt, other.shirt)
+            && color_eq(self.pants, other.pants)
+    }
+}
+
 impl Default for PlayerColors {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerRecord {
+    pub id: Id,
+    pub pos: Vec2<f32>,
+    pub colors: PlayerColors,
+    pub hp: Option<f32>,
+    pub holding: Option<Box<Entity>>,
+    #[serde(default)]
+    pub inventory: Option<CompInventory>,
+}
+
+impl PlayerRecord {
+    pub fn new(entity: &Entity) -> Self {
+        Self {
+            id: entity.id,
+            pos: entity.pos.unwrap(),
+            colors: match &entity.controller {
+                Some(CompController::Player { colors }) => colors.clone(),
+                _ => PlayerColors::new(),
+            },
+            hp: entity.hp.as_ref().map(|hp| hp.current_hp),
+            holding: entity
+                .holding
+                .as_ref()
+                .and_then(|holding| holding.entity.clone()),
+            inventory: entity.inventory.clone(),
+        }
+    }
+    pub fn apply(&self, entity: &mut Entity) {
+        if let Some(CompController::Player { colors }) = entity.controller.as_mut() {
+            *colors = self.colors.clone();
+        }
+        if let (Some(hp), Some(current_hp)) = (entity.hp.as_mut(), self.hp) {
+            hp.current_hp = current_hp;
+        }
+        if let Some(holding) = entity.holding.as_mut() {
+            holding.entity = self.holding.clone();
+        }
+        if let (Some(inventory), Some(saved)) = (entity.inventory.as_mut(), &self.inventory) {
+            if saved.slots == inventory.slots {
+                *inventory = saved.clone();
+            } else {
+                // The slot count changed, keep the items in order as long as they fit
+                let mut items = saved.items.iter().flatten().cloned();
+                let active_slot = inventory.active_slot;
+                for (slot, item) in inventory.items.iter_mut().enumerate() {
+                    if slot != active_slot {
+                        *item = items.next();
+                    }
+                }
+                let lost = items.count();
+                if lost > 0 {
+                    warn!(
+                        "{} inventory items of player {:?} did not fit into {} slots",
+                        lost, self.id, inventory.slots
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct PlayerRecords {
+    pub records: HashMap<String, PlayerRecord>,
+}
+
+impl util::Versioned for PlayerRecords {
+    const VERSION: u32 = 1;
+    fn migrate(version: u32, data: &[u8]) -> Result<Self, anyhow::Error> {
+        match version {
+            0 => legacy::v0::load::<legacy::v0::PlayerRecords, _>(data),
+            _ => Err(anyhow::Error::from(legacy::UnknownFormatVersionError {
+                version,
+            })),
+        }
+    }
+}
@@ -1,16 +1,42 @@
 use super::*;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Recipe {
+    #[serde(default)]
+    pub id: String,
     pub ingredient1: Option<EntityType>,
     pub ingredient2: Option<EntityType>,
     pub result1: Option<EntityType>,
     pub result2: Option<EntityType>,
     pub conditions: Option<Biome>,
     pub craft_time: f32,
+    #[serde(default)]
+    pub loot_table: Option<String>,
+    // Wears the held tool down instead of replacing it, result1 must be ingredient1
+    #[serde(default)]
+    pub durability_cost: Option<u32>,
+    #[serde(default)]
+    pub time_of_day: Option<TimeRange>,
 }
 
 impl Recipe {
+    // Recipes without an explicit id are identified by their ingredients
+    pub fn default_id(&self) -> String {
+        let name = |entity_type: &Option<EntityType>, empty: &str| {
+            entity_type
+                .as_ref()
+                .map_or(empty.to_owned(), |entity_type| entity_type.to_string())
+        };
+        let mut id = format!(
+            "{}+{}",
+            name(&self.ingredient1, "EmptyHand"),
+            name(&self.ingredient2, "EmptySpace")
+        );
+        if let Some(biome) = &self.conditions {
+            id += &format!("@{}", biome);
+        }
+        id
+    }
     pub fn ingredients_equal(
         &self,
         ingredient1: Option<EntityType>,
@@ -24,24 +50,19 @@ impl Recipe {
                 _ => conditions == self.conditions,
             }
     }
-    pub fn is_relevant(&self, player_id: Id, view: &ClientView) -> bool {
+    pub fn is_relevant(&self, player: &Entity) -> bool {
         self.ingredient1.as_ref()
-            == view
-                .entities
-                .iter()
-                .find(|p| p.id == player_id)
-                .and_then(|p| {
-                    p.holding
-                        .as_ref()
-                        .unwrap()
-                        .entity
-                        .as_ref()
-                        .map(|e| &e.entity_type)
-                })
+            == player
+                .holding
+                .as_ref()
+                .unwrap()
+                .entity
+                .as_ref()
+                .map(|e| &e.entity_type)
     }
     pub fn to_string(&self) -> String {
         format!(
-            "{} + {} = {}{}",
+            "{} + {} = {}{}{}",
             self.ingredient1
                 .as_ref()
                 .map_or("Empty Hand".to_owned(), |item| item.to_string()),
@@ -49,9 +70,11 @@ impl Recipe {
                 .as_ref()
                 .map_or("Empty Space".to_owned(), |s| s.to_string()),
             if self.result1 == self.ingredient1 || self.result1.is_none() {
-                self.result2
-                    .as_ref()
-                    .map_or("None".to_owned(), |s| s.to_string())
+                match (&self.result2, &self.loot_table) {
+                    (Some(result2), _) => result2.to_string(),
+                    (None, Some(loot_table)) => loot_table.clone(),
+                    (None, None) => "None".to_owned(),
+                }
             } else {
                 if self.ingredient2 == self.result2 || self.result2.is_none() {
                     self.result1
@@ -69,6 +92,11 @@ impl Recipe {
                 format!(" (only in {})", biome)
             } else {
                 "".to_owned()
+            },
+            if let Some(time_of_day) = &self.time_of_day {
+                format!(" (only at {})", time_of_day)
+            } else {
+                "".to_owned()
             }
         )
     }
@@ -8,38 +8,202 @@ pub struct ResourcePack {
     pub entity_components: HashMap<EntityType, EntityComponents>,
     pub entity_generation: HashMap<Biome, Vec<ItemGeneration>>,
     pub recipes: Vec<Recipe>,
+    pub loot_tables: HashMap<String, LootTable>,
 }
 
+// The packs a world was created with, in load order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PackList(pub Vec<String>);
+
+// Plain json before the format was versioned
+impl util::Versioned for PackList {
+    const VERSION: u32 = 1;
+    fn migrate(version: u32, data: &[u8]) -> Result<Self, anyhow::Error> {
+        match version {
+            0 => Ok(serde_json::from_slice(data)?),
+            _ => Err(anyhow::Error::from(legacy::UnknownFormatVersionError {
+                version,
+            })),
+        }
+    }
+}
+
+// Changes a pack makes to recipes and entities of the packs loaded before it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackPatches {
+    // A null patch disables the recipe
+    pub recipes: HashMap<String, Option<serde_json::Value>>,
+    pub entities: HashMap<EntityType, serde_json::Value>,
+}
+
+impl PackPatches {
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, std::io::Error> {
+        let server_path = path.as_ref().join("server");
+        Ok(Self {
+            recipes: load_or_default(server_path.join("recipe-patches.json"))?,
+            entities: load_or_default(server_path.join("entity-patches.json"))?,
+        })
+    }
+}
+
+fn load_or_default<T: Default + for<'de> Deserialize<'de>>(
+    path: impl AsRef<std::path::Path>,
+) -> std::io::Result<T> {
+    match std::fs::File::open(path.as_ref()) {
+        Ok(file) => {
+            let reader = std::io::BufReader::new(file);
+            Ok(serde_json::from_reader(reader)?)
+        }
+        Err(err) => match err.kind() {
+            std::io::ErrorKind::NotFound => Ok(T::default()),
+            _ => Err(err),
+        },
+    }
+}
+
+fn patch<T: Serialize + for<'de> Deserialize<'de>>(
+    value: &T,
+    patch: &serde_json::Value,
+) -> Result<T, serde_json::Error> {
+    let mut json = serde_json::to_value(value)?;
+    util::merge_patch(&mut json, patch);
+    serde_json::from_value(json)
+}
+
+#[derive(Debug, Clone)]
+struct PackConflictError {
+    packs: (String, String),
+    kind: &'static str,
+    name: String,
+}
+
+impl std::fmt::Display for PackConflictError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Packs {} and {} both change {} {}, one of them must list the other in load_after.",
+            self.packs.0, self.packs.1, self.kind, self.name
+        )
+    }
+}
+
+impl std::error::Error for PackConflictError {}
+
 impl ResourcePack {
     pub fn load_all(
         path: impl AsRef<std::path::Path>,
-    ) -> Result<(Vec<String>, Self), std::io::Error> {
+    ) -> Result<(Vec<String>, Self), anyhow::Error> {
         let mut packs = Vec::new();
         let mut resource_pack = Self::empty();
-        for pack in std::fs::read_dir(path.as_ref())? {
-            let pack = pack?;
-            packs.push(pack.file_name().to_str().unwrap().to_owned());
-            resource_pack.merge(Self::load(pack.path())?);
+        let mut owners = HashMap::new();
+        let mut loaded_after: HashMap<String, HashSet<String>> = HashMap::new();
+        for (manifest, pack_path) in PackManifest::load_order(path)? {
+            info!("Loading pack {} {}", manifest.id, manifest.version);
+            // Packs this one is ordered after, directly or through other packs
+            let mut after = HashSet::new();
+            for other in manifest.dependencies.iter().chain(&manifest.load_after) {
+                if let Some(other_after) = loaded_after.get(other) {
+                    after.insert(other.clone());
+                    after.extend(other_after.iter().cloned());
+                }
+            }
+            let pack = Self::load(&pack_path)?;
+            let patches = PackPatches::load(&pack_path)?;
+            pack.check_conflicts(&manifest, &after, &patches, &mut owners)?;
+            resource_pack.merge(pack);
+            resource_pack.apply_patches(&manifest, patches)?;
+            loaded_after.insert(manifest.id.clone(), after);
+            packs.push(manifest.id);
+        }
+        for (id, loot_table) in &mut resource_pack.loot_tables {
+            loot_table.skip_empty_ranges(id);
         }
         Ok((packs, resource_pack))
     }
+    // Two packs may only change the same thing if their load order is fixed
+    fn check_conflicts(
+        &self,
+        manifest: &PackManifest,
+        after: &HashSet<String>,
+        patches: &PackPatches,
+        owners: &mut HashMap<(&'static str, String), String>,
+    ) -> Result<(), anyhow::Error> {
+        let keys = self
+            .biome_properties
+            .keys()
+            .map(|biome| ("biome properties", biome.to_string()))
+            .chain(
+                self.biome_generation
+                    .keys()
+                    .map(|biome| ("biome generation", biome.to_string())),
+            )
+            .chain(
+                self.world_parameters
+                    .keys()
+                    .map(|parameter| ("world parameter", parameter.0.clone())),
+            )
+            .chain(
+                self.entity_components
+                    .keys()
+                    .map(|entity_type| ("entity", entity_type.to_string())),
+            )
+            .chain(
+                self.entity_generation
+                    .keys()
+                    .map(|biome| ("entity generation", biome.to_string())),
+            )
+            .chain(
+                self.recipes
+                    .iter()
+                    .map(|recipe| ("recipe", recipe.id.clone())),
+            )
+            .chain(self.loot_tables.keys().map(|id| ("loot table", id.clone())))
+            .chain(patches.recipes.keys().map(|id| ("recipe", id.clone())))
+            .chain(
+                patches
+                    .entities
+                    .keys()
+                    .map(|entity_type| ("entity", entity_type.to_string())),
+            );
+        for key in keys {
+            if let Some(owner) = owners.insert(key.clone(), manifest.id.clone()) {
+                if owner == manifest.id {
+                    continue;
+                }
+                let (kind, name) = key;
+                if !after.contains(&owner) {
+                    return Err(anyhow::Error::from(PackConflictError {
+                        packs: (owner, manifest.id.clone()),
+                        kind,
+                        name,
+                    }));
+                }
+                info!(
+                    "Pack {} overrides {} {} from {}",
+                    manifest.id, kind, name, owner
+                );
+            }
+        }
+        Ok(())
+    }
     fn load(path: impl AsRef<std::path::Path>) -> Result<Self, std::io::Error> {
         let path = path.as_ref();
         let server_path = path.join("server");
 
-        fn load_or_default<T: Default + for<'de> Deserialize<'de>>(
-            path: impl AsRef<std::path::Path>,
-        ) -> std::io::Result<T> {
-            match std::fs::File::open(path.as_ref()) {
-                Ok(file) => {
-                    let reader = std::io::BufReader::new(file);
-                    Ok(serde_json::from_reader(reader)?)
-                }
-                Err(err) => match err.kind() {
-                    std::io::ErrorKind::NotFound => Ok(T::default()),
-                    _ => Err(err),
-                },
+        let mut recipes: Vec<Recipe> = Vec::new();
+        for mut recipe in load_or_default::<Vec<Recipe>>(server_path.join("recipes.json"))? {
+            if recipe.id.is_empty() {
+                recipe.id = recipe.default_id();
             }
+            if let Some(index) = recipes.iter().position(|other| other.id == recipe.id) {
+                warn!(
+                    "Recipe {} is defined twice in {:?}, using the last one",
+                    recipe.id, path
+                );
+                recipes.remove(index);
+            }
+            recipes.push(recipe);
         }
 
         Ok(Self {
@@ -47,7 +211,8 @@ impl ResourcePack {
             biome_generation: load_or_default(server_path.join("generation-biomes.json"))?,
             world_parameters: load_or_default(server_path.join("world-parameters.json"))?,
             entity_generation: load_or_default(server_path.join("generation-entities.json"))?,
-            recipes: load_or_default(server_path.join("recipes.json"))?,
+            recipes,
+            loot_tables: load_or_default(server_path.join("loot-tables.json"))?,
             entity_components: load_or_default(server_path.join("entities.json"))?,
         })
     }
@@ -59,6 +224,7 @@ impl ResourcePack {
             entity_components: HashMap::new(),
             entity_generation: HashMap::new(),
             recipes: Vec::new(),
+            loot_tables: HashMap::new(),
         }
     }
     pub fn merge(&mut self, resource_pack: ResourcePack) {
@@ -69,6 +235,60 @@ impl ResourcePack {
             .extend(resource_pack.entity_components);
         self.entity_generation
             .extend(resource_pack.entity_generation);
-        self.recipes.extend(resource_pack.recipes);
+        self.loot_tables.extend(resource_pack.loot_tables);
+        for recipe in resource_pack.recipes {
+            match self.recipes.iter_mut().find(|other| other.id == recipe.id) {
+                Some(other) => *other = recipe,
+                None => self.recipes.push(recipe),
+            }
+        }
+    }
+    fn apply_patches(
+        &mut self,
+        manifest: &PackManifest,
+        patches: PackPatches,
+    ) -> Result<(), anyhow::Error> {
+        for (id, recipe_patch) in &patches.recipes {
+            let index = match self.recipes.iter().position(|recipe| recipe.id == *id) {
+                Some(index) => index,
+                None => {
+                    warn!("Pack {} patches unknown recipe {}", manifest.id, id);
+                    continue;
+                }
+            };
+            match recipe_patch {
+                Some(recipe_patch) => {
+                    let recipe = &mut self.recipes[index];
+                    *recipe = patch(&*recipe, recipe_patch).map_err(|e| {
+                        anyhow!("Pack {} failed to patch recipe {}: {}", manifest.id, id, e)
+                    })?;
+                    recipe.id = id.clone();
+                }
+                None => {
+                    self.recipes.remove(index);
+                }
+            }
+        }
+        for (entity_type, entity_patch) in &patches.entities {
+            let components = match self.entity_components.get_mut(entity_type) {
+                Some(components) => components,
+                None => {
+                    warn!(
+                        "Pack {} patches unknown entity {}",
+                        manifest.id, entity_type
+                    );
+                    continue;
+                }
+            };
+            *components = patch(&*components, entity_patch).map_err(|e| {
+                anyhow!(
+                    "Pack {} failed to patch entity {}: {}",
+                    manifest.id,
+                    entity_type,
+                    e
+                )
+            })?;
+        }
+        Ok(())
     }
 }
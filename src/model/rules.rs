@@ -5,9 +5,30 @@ pub struct Rules {
     pub client_view_distance: f32,
     pub campfire_light: f32,
     pub torch_light: f32,
+    // Chance in percent for an empty tile to regrow an entity each second
     pub regeneration_percent: f32,
+    pub regenerate_unloaded_chunks: bool,
+    pub day_length: f32,
     pub statue_light: f32,
     pub sound_distance: f32,
     pub generation_distance: usize,
     pub spawn_area: f32,
+    pub held_item_on_disconnect: HeldItemPolicy,
+}
+
+impl Rules {
+    pub fn light_radius(&self, light: LightSource) -> f32 {
+        match light {
+            LightSource::Campfire => self.campfire_light,
+            LightSource::Torch => self.torch_light,
+            LightSource::Statue => self.statue_light,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub enum HeldItemPolicy {
+    Drop,
+    Keep,
+    Destroy,
 }
@@ -1,7 +1,43 @@
 use super::*;
 
 impl Model {
-    pub fn spawn_player(&mut self) -> Id {
+    pub fn spawn_player(&mut self, token: &str) -> Id {
+        if let Some(record) = self.players.records.get(token).cloned() {
+            if self.chunked_world.get_entity(record.id).is_none() {
+                self.restore_player(token, &record);
+                return record.id;
+            }
+            warn!("Player is already online, spawning a new character");
+            return self.spawn_new_player(None);
+        }
+        self.spawn_new_player(Some(token))
+    }
+    fn restore_player(&mut self, token: &str, record: &PlayerRecord) {
+        let player_id = record.id;
+        let pos = record.pos;
+        self.chunked_world.set_load_area_for(
+            player_id,
+            &mut self.id_generator,
+            Some(AABB::pos_size(pos, vec2(0.0, 0.0))),
+        );
+        let entity_type = EntityType("Player".to_owned());
+        let mut entity = Entity::new(
+            player_id,
+            &entity_type,
+            Some(pos),
+            &self.resource_pack.entity_components,
+        );
+        entity.load_area.as_mut().unwrap().load_area = AABB::pos_size(pos, vec2(0.0, 0.0));
+        record.apply(&mut entity);
+
+        self.sounds.insert(player_id, vec![]);
+        self.client_entities.insert(player_id, HashMap::new());
+        self.player_tokens.insert(player_id, token.to_owned());
+        self.chunked_world
+            .insert_entity(entity, &mut self.id_generator)
+            .unwrap();
+    }
+    fn spawn_new_player(&mut self, token: Option<&str>) -> Id {
         let player_id = self.id_generator.gen();
         if let Some(pos) = self.get_spawnable_pos(player_id, vec2(0.0, 0.0), self.rules.spawn_area)
         {
@@ -19,6 +55,10 @@ impl Model {
             }
 
             self.sounds.insert(player_id, vec![]);
+            self.client_entities.insert(player_id, HashMap::new());
+            if let Some(token) = token {
+                self.player_tokens.insert(player_id, token.to_owned());
+            }
             self.chunked_world
                 .insert_entity(entity, &mut self.id_generator)
                 .unwrap();
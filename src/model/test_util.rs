@@ -0,0 +1,40 @@
+use super::*;
+
+// Lake and Forest split by height, with the entity types Forest generates
+pub fn test_resource_pack() -> ResourcePack {
+    serde_json::from_value(serde_json::json!({
+        "biome_properties": {
+            "Lake": { "collidable": true, "spawnable": false },
+            "Forest": { "collidable": false, "spawnable": true }
+        },
+        "biome_generation": {
+            "Lake": { "world_parameters": { "Height": [-1, 0] } },
+            "Forest": { "world_parameters": { "Height": [0, 1] } }
+        },
+        "world_parameters": {
+            "Height": {
+                "min_value": -1,
+                "max_value": 1,
+                "scale": 20,
+                "octaves": 3,
+                "lacunarity": 2,
+                "persistance": 0.5
+            }
+        },
+        "entity_components": {
+            "Player": { "controller": { "Player": {} } },
+            "Rock": { "size": 0.5, "collidable": { "collision_type": "Static" } },
+            "Tree": { "size": 0.5, "collidable": { "collision_type": "Static" } }
+        },
+        "entity_generation": {
+            "Forest": [
+                { "entity_type": "Tree", "weight": 1 },
+                { "entity_type": "Rock", "weight": 1 },
+                { "entity_type": null, "weight": 2 }
+            ]
+        },
+        "recipes": [],
+        "loot_tables": {}
+    }))
+    .unwrap()
+}
@@ -2,6 +2,13 @@ use super::*;
 
 impl Model {
     pub fn tick(&mut self) {
+        self.current_time.ticks += 1;
+        let (world_time, time_of_day) = (self.world_time(), self.time_of_day());
+        self.chunked_world.set_time(world_time, time_of_day);
+        let ticks_per_second = (self.ticks_per_second.round() as usize).max(1);
+        if self.current_time.ticks % ticks_per_second == 0 {
+            self.chunked_world.regenerate(&mut self.id_generator, 1.0);
+        }
         let ids: Vec<Id> = self.chunked_world.entities().map(|e| e.id).collect();
         for id in ids {
             if let Some(entity) = self.chunked_world.get_entity(id) {
@@ -17,11 +24,18 @@ impl Model {
 
         // Collide with entities
         if entity.components.collidable.is_some() {
-            for other in self
+            let others: Vec<Id> = self
                 .chunked_world
-                .entities_mut()
-                .filter(|other| other.id != entity_id && other.components.collidable.is_some())
-            {
+                .find_range(
+                    entity.pos.unwrap(),
+                    entity.size.unwrap() + self.max_entity_size,
+                    |other| other.id != entity_id && other.components.collidable.is_some(),
+                )
+                .into_iter()
+                .map(|other| other.id)
+                .collect();
+            for id in others {
+                let other = self.chunked_world.get_entity_mut(id).unwrap();
                 Self::check_entity_collision(&mut entity, other);
             }
         }
@@ -107,9 +121,11 @@ impl Model {
     fn entity_action_decide(&self, entity: &mut Entity) {
         match entity.controller.as_ref().unwrap() {
             CompController::Player { .. } => (),
-            CompController::BiomeRandomWalker { biome } => {
+            CompController::BiomeRandomWalker { biome, active_time } => {
+                let active = active_time
+                    .map_or(true, |active_time| active_time.contains(self.time_of_day()));
                 let entity_action = entity.action.as_ref().unwrap();
-                if entity_action.current_action.is_none() {
+                if active && entity_action.current_action.is_none() {
                     let mut random = global_rng();
                     let random_pos = entity.pos.unwrap()
                         + vec2(
@@ -156,27 +172,44 @@ impl Model {
                 } => {
                     let time_left = time_left - 1.0 / self.ticks_per_second;
                     if time_left <= 0.0 {
-                        let hand_entity = &mut entity.holding.as_mut().unwrap().entity;
+                        let hand_entity = entity.holding.as_mut().unwrap().entity.take();
                         let (conditions, ingredient2) =
                             self.get_target_conditions(&target.target_type);
                         if recipe.ingredients_equal(
-                            hand_entity.take().map(|e| e.entity_type),
+                            hand_entity.as_ref().map(|e| e.entity_type.clone()),
                             ingredient2,
                             conditions,
                         ) {
-                            *hand_entity = match &recipe.result1 {
-                                Some(entity_type) => Some(Box::new(Entity::new(
-                                    self.id_generator.gen(),
-                                    entity_type,
-                                    None,
-                                    &self.resource_pack.entity_components,
-                                ))),
-                                None => None,
+                            let pos = entity.pos.unwrap();
+                            let hand_entity = match (recipe.durability_cost, hand_entity) {
+                                (Some(cost), Some(tool)) if tool.durability.is_some() => {
+                                    self.wear_tool(tool, cost, pos)
+                                }
+                                (_, hand_entity) => {
+                                    // The ingredient is used up, the items stored in it are not
+                                    if let Some(hand_entity) = &hand_entity {
+                                        self.drop_contents(hand_entity, pos);
+                                    }
+                                    match &recipe.result1 {
+                                        Some(entity_type) => Some(Box::new(Entity::new(
+                                            self.id_generator.gen(),
+                                            entity_type,
+                                            None,
+                                            &self.resource_pack.entity_components,
+                                        ))),
+                                        None => None,
+                                    }
+                                }
                             };
+                            entity.holding.as_mut().unwrap().entity = hand_entity;
                             let target_pos = match &target.target_type {
                                 TargetType::Entity { id } => {
                                     let entity = self.chunked_world.remove_entity(*id);
-                                    entity.map(|e| e.pos.unwrap())
+                                    entity.map(|e| {
+                                        let pos = e.pos.unwrap();
+                                        self.drop_contents(&e, pos);
+                                        pos
+                                    })
                                 }
                                 TargetType::Position { pos } => Some(*pos),
                             };
@@ -184,8 +217,13 @@ impl Model {
                                 if let Some(entity_type) = recipe.result2 {
                                     self.spawn_entity(&entity_type, target_pos);
                                 }
+                                if let Some(loot_table) = &recipe.loot_table {
+                                    self.spawn_loot(loot_table, target_pos);
+                                }
                             }
                             self.play_sound(Sound::Craft, entity.pos.unwrap());
+                        } else {
+                            entity.holding.as_mut().unwrap().entity = hand_entity;
                         }
                         let entity_action = entity.action.as_mut().unwrap();
                         entity_action.current_action = entity_action.next_action.take();
@@ -230,13 +268,21 @@ impl Model {
                                     .map(|e| e.entity_type.clone());
                                 let (conditions, ingredient2) =
                                     self.get_target_conditions(&target.target_type);
-                                let recipe = self.resource_pack.recipes.iter().find(|recipe| {
-                                    recipe.ingredients_equal(
-                                        ingredient1.clone(),
-                                        ingredient2.clone(),
-                                        conditions.clone(),
-                                    )
-                                });
+                                // Recipes for a specific biome take priority over general ones
+                                let recipe = self
+                                    .resource_pack
+                                    .recipes
+                                    .iter()
+                                    .filter(|recipe| {
+                                        recipe.ingredients_equal(
+                                            ingredient1.clone(),
+                                            ingredient2.clone(),
+                                            conditions.clone(),
+                                        ) && recipe.time_of_day.map_or(true, |time_of_day| {
+                                            time_of_day.contains(self.time_of_day())
+                                        })
+                                    })
+                                    .min_by_key(|recipe| recipe.conditions.is_none());
                                 if let Some(recipe) = recipe {
                                     let entity_action = entity.action.as_mut().unwrap();
                                     entity_action.current_action = Some(EntityAction::Crafting {
@@ -301,14 +347,15 @@ impl Model {
                     };
                     let (_, reached) = self.reached_target(entity, &target);
                     if reached {
-                        let hand_item = &mut entity.holding.as_mut().unwrap().entity;
                         let mut ground_entity = self.chunked_world.remove_entity(id);
-                        if let None = hand_item {
-                            if let Some(e) = &ground_entity {
-                                if e.pickable.is_some() {
-                                    *hand_item = Some(Box::new(ground_entity.take().unwrap()));
+                        if let Some(e) = ground_entity.take() {
+                            if e.pickable.is_some() {
+                                ground_entity = entity.store_item(Box::new(e)).map(|e| *e);
+                                if ground_entity.is_none() {
                                     self.play_sound(Sound::PickUp, entity.pos.unwrap());
                                 }
+                            } else {
+                                ground_entity = Some(e);
                             }
                         }
                         if let Some(item) = ground_entity {
@@ -322,6 +369,59 @@ impl Model {
                         entity_action.next_action = Some(EntityAction::PickUp { id });
                     }
                 }
+                EntityAction::Deposit { id } => {
+                    let target = ActionTarget {
+                        interaction_type: InteractionType::Interact,
+                        target_type: TargetType::Entity { id },
+                    };
+                    let (_, reached) = self.reached_target(entity, &target);
+                    if reached {
+                        let hand_entity = &mut entity.holding.as_mut().unwrap().entity;
+                        let container = self
+                            .chunked_world
+                            .get_entity_mut(id)
+                            .and_then(|container| container.container.as_mut());
+                        let item = hand_entity.take();
+                        match (container, item) {
+                            (Some(container), Some(item))
+                                if container.accepts(&item.entity_type) =>
+                            {
+                                container.items.push(item);
+                                self.play_sound(Sound::PutDown, entity.pos.unwrap());
+                            }
+                            (_, item) => *hand_entity = item,
+                        }
+                    } else {
+                        let entity_action = entity.action.as_mut().unwrap();
+                        entity_action.current_action = Some(EntityAction::MovingTo { target });
+                        entity_action.next_action = Some(EntityAction::Deposit { id });
+                    }
+                }
+                EntityAction::Withdraw { id, index } => {
+                    let target = ActionTarget {
+                        interaction_type: InteractionType::Interact,
+                        target_type: TargetType::Entity { id },
+                    };
+                    let (_, reached) = self.reached_target(entity, &target);
+                    if reached {
+                        let container = self
+                            .chunked_world
+                            .get_entity_mut(id)
+                            .and_then(|container| container.container.as_mut())
+                            .filter(|container| index < container.items.len());
+                        if let Some(container) = container {
+                            let item = container.items.remove(index);
+                            match entity.store_item(item) {
+                                Some(item) => container.items.insert(index, item),
+                                None => self.play_sound(Sound::PickUp, entity.pos.unwrap()),
+                            }
+                        }
+                    } else {
+                        let entity_action = entity.action.as_mut().unwrap();
+                        entity_action.current_action = Some(EntityAction::MovingTo { target });
+                        entity_action.next_action = Some(EntityAction::Withdraw { id, index });
+                    }
+                }
             }
         } else {
             let entity_action = entity.action.as_mut().unwrap();
@@ -421,6 +521,30 @@ impl Model {
             .unwrap();
     }
 
+    // Items stored in a tool that breaks are dropped at the given position
+    fn wear_tool(
+        &mut self,
+        mut tool: Box<Entity>,
+        cost: u32,
+        pos: Vec2<f32>,
+    ) -> Option<Box<Entity>> {
+        let durability = tool.durability.as_mut().unwrap();
+        durability.current_durability = durability.current_durability.saturating_sub(cost);
+        if durability.current_durability > 0 {
+            return Some(tool);
+        }
+        let broken_result = durability.broken_result.clone();
+        self.drop_contents(&tool, pos);
+        broken_result.map(|entity_type| {
+            Box::new(Entity::new(
+                self.id_generator.gen(),
+                &entity_type,
+                None,
+                &self.resource_pack.entity_components,
+            ))
+        })
+    }
+
     fn damage_entity(&mut self, entity_id: Id, weapon: &CompWeapon) {
         if let Some(entity) = self.chunked_world.get_entity_mut(entity_id) {
             if let Some(hp) = entity.hp.as_mut() {
@@ -433,6 +557,27 @@ impl Model {
     }
 
     fn kill_entity(&mut self, entity_id: Id) {
-        self.chunked_world.remove_entity(entity_id);
+        if let Some(entity) = self.chunked_world.remove_entity(entity_id) {
+            if let Some(pos) = entity.pos {
+                if let Some(loot_table) = &entity.loot_table {
+                    self.spawn_loot(loot_table, pos);
+                }
+                self.drop_contents(&entity, pos);
+            }
+        }
+    }
+
+    // Puts the items of a removed container back into the world,
+    // held containers have no position of their own
+    fn drop_contents(&mut self, entity: &Entity, pos: Vec2<f32>) {
+        if let Some(container) = &entity.container {
+            for item in &container.items {
+                let mut item = (**item).clone();
+                item.pos = Some(pos);
+                self.chunked_world
+                    .insert_entity(item, &mut self.id_generator)
+                    .unwrap();
+            }
+        }
     }
 }
@@ -0,0 +1,30 @@
+use super::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TickReport {
+    pub ticks_per_second: f64,
+    pub average_tick_time: f64,
+    pub max_tick_time: f64,
+    pub overruns: usize,
+    pub skipped_ticks: usize,
+}
+
+impl TickReport {
+    pub fn is_lagging(&self) -> bool {
+        self.overruns != 0 || self.skipped_ticks != 0
+    }
+}
+
+impl Display for TickReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "TPS: {:.1}, tick time avg {:.2} ms / max {:.2} ms, {} overruns, {} skipped ticks",
+            self.ticks_per_second,
+            self.average_tick_time * 1000.0,
+            self.max_tick_time * 1000.0,
+            self.overruns,
+            self.skipped_ticks,
+        )
+    }
+}
@@ -0,0 +1,62 @@
+use super::*;
+
+// Times are fractions of the day, 0 is midnight and 0.5 is noon.
+// The range wraps around midnight if it starts later than it ends.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub from: f32,
+    pub to: f32,
+}
+
+impl TimeRange {
+    pub fn contains(&self, time_of_day: f32) -> bool {
+        if self.from <= self.to {
+            self.from <= time_of_day && time_of_day < self.to
+        } else {
+            time_of_day >= self.from || time_of_day < self.to
+        }
+    }
+    pub fn is_valid(&self) -> bool {
+        (0.0..=1.0).contains(&self.from) && (0.0..=1.0).contains(&self.to)
+    }
+}
+
+impl Display for TimeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let clock = |time: f32| {
+            let minutes = (time * 24.0 * 60.0).round() as u32;
+            format!("{:02}:{:02}", minutes / 60, minutes % 60)
+        };
+        write!(f, "{}-{}", clock(self.from), clock(self.to))
+    }
+}
+
+// Ticks since the world was created, saved so the day continues after a restart
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorldTime {
+    pub ticks: usize,
+}
+
+impl util::Versioned for WorldTime {
+    const VERSION: u32 = 1;
+    fn migrate(version: u32, _data: &[u8]) -> Result<Self, anyhow::Error> {
+        Err(anyhow::Error::from(legacy::UnknownFormatVersionError {
+            version,
+        }))
+    }
+}
+
+impl Model {
+    // Seconds the world has been running
+    pub fn world_time(&self) -> f64 {
+        self.current_time.ticks as f64 / self.ticks_per_second as f64
+    }
+    pub fn time_of_day(&self) -> f32 {
+        let day_ticks = (self.rules.day_length * self.ticks_per_second).round() as usize;
+        // A day length of 0 stops the cycle at noon
+        if day_ticks == 0 {
+            return 0.5;
+        }
+        (self.current_time.ticks % day_ticks) as f32 / day_ticks as f32
+    }
+}
@@ -9,50 +9,48 @@ pub struct BiomeGeneration {
 pub struct ItemGeneration {
     pub entity_type: Option<EntityType>,
     pub weight: usize,
+    #[serde(default)]
+    pub time_of_day: Option<TimeRange>,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorldParameter(pub String);
 
 pub struct WorldGen {
+    seed: u32,
     world_parameters: HashMap<WorldParameter, MultiNoise>,
     biome_generation: HashMap<Biome, BiomeGeneration>,
+    fallback_biome: Option<Biome>,
     entity_generation: HashMap<Biome, Vec<ItemGeneration>>,
     pub entity_components: HashMap<EntityType, EntityComponents>,
 }
 
 impl WorldGen {
-    pub fn new(seed: u32, resource_pack: &ResourcePack) -> Self {
+    pub fn new(seed: u32, fallback_biome: Option<Biome>, resource_pack: &ResourcePack) -> Self {
         let seed_noise = ::noise::OpenSimplex::new().set_seed(seed);
         Self {
+            seed,
             world_parameters: resource_pack
                 .world_parameters
                 .iter()
                 .map(|(parameter, multi_noise_properties)| {
-                    fn hash<T>(obj: T) -> u32
-                    where
-                        T: std::hash::Hash,
-                    {
-                        use std::hash::*;
-                        let mut hasher = siphasher::sip::SipHasher::new();
-                        obj.hash(&mut hasher);
-                        hasher.finish() as u32
-                    }
                     (
                         parameter.clone(),
                         MultiNoise::new(
-                            (seed_noise.get([hash(parameter) as f64, 0.0]) * 1e5).abs() as u32,
+                            (seed_noise.get([hash(parameter) as u32 as f64, 0.0]) * 1e5).abs()
+                                as u32,
                             multi_noise_properties,
                         ),
                     )
                 })
                 .collect(),
             biome_generation: resource_pack.biome_generation.clone(),
+            fallback_biome,
             entity_generation: resource_pack.entity_generation.clone(),
             entity_components: resource_pack.entity_components.clone(),
         }
     }
-    pub fn generate_tile(&self, pos: Vec2<i64>) -> (Tile, Option<EntityType>) {
+    pub fn generate_tile(&self, pos: Vec2<i64>) -> Tile {
         let world_parameters: HashMap<WorldParameter, f32> = self
             .world_parameters
             .iter()
@@ -63,42 +61,162 @@ impl WorldGen {
                 )
             })
             .collect();
-        let biome = self
-            .biome_generation
-            .iter()
-            .filter_map(|(biome, biome_gen)| {
-                let mut total_score = 0.0;
-                for (world_parameter, zone) in &biome_gen.world_parameters {
-                    let world_parameter_value = world_parameters[world_parameter];
-                    let world_parameter_score =
-                        (world_parameter_value - zone.0).min(zone.1 - world_parameter_value);
-                    if world_parameter_score < 0.0 {
-                        return None;
-                    } else {
-                        total_score += world_parameter_score;
-                    }
-                }
-                Some((biome, total_score))
-            })
-            .min_by_key(|(_, score)| r32(*score))
-            .unwrap()
-            .0
+        let biome = select_biome(&self.biome_generation, &world_parameters)
+            .or_else(|| self.fallback_biome.as_ref())
+            .or_else(|| nearest_biome(&self.biome_generation, &world_parameters))
+            .expect("No biomes to generate")
             .clone();
 
-        let tile = Tile {
-            biome: biome.clone(),
+        Tile {
+            biome,
             world_parameters,
-        };
-
-        let entity_type = self
+        }
+    }
+    // Only depends on the seed, entries limited to a time of day are left to regrowth
+    pub fn generate_entity(&self, tile: &Tile, pos: Vec2<i64>) -> Option<EntityType> {
+        self.roll_entity(tile, None, hash((self.seed, pos.x, pos.y)))
+    }
+    // Rolled randomly to regrow resources, entries limited to a time of day are only
+    // considered at that time
+    pub fn regenerate_entity(&self, tile: &Tile, time_of_day: f32) -> Option<EntityType> {
+        self.roll_entity(tile, Some(time_of_day), global_rng().gen())
+    }
+    // Entity types that never generate naturally were built or crafted by players
+    pub fn is_generated(&self, entity_type: &EntityType) -> bool {
+        self.entity_generation
+            .values()
+            .flatten()
+            .any(|item| item.entity_type.as_ref() == Some(entity_type))
+    }
+    fn roll_entity(&self, tile: &Tile, time_of_day: Option<f32>, roll: u64) -> Option<EntityType> {
+        let items: Vec<&ItemGeneration> = self
             .entity_generation
-            .get(&biome)
-            .map(|gen| {
-                gen.choose_weighted(&mut global_rng(), |item| item.weight)
-                    .unwrap()
+            .get(&tile.biome)?
+            .iter()
+            .filter(|item| match (item.time_of_day, time_of_day) {
+                (None, _) => true,
+                (Some(time_range), Some(time_of_day)) => time_range.contains(time_of_day),
+                (Some(_), None) => false,
             })
-            .map(|item| item.entity_type.clone())
-            .flatten();
-        (tile, entity_type)
+            .collect();
+        choose_weighted(&items, roll).and_then(|item| item.entity_type.clone())
+    }
+}
+
+fn zone_score(biome_gen: &BiomeGeneration, world_parameter: &WorldParameter, value: f32) -> f32 {
+    let zone = biome_gen.world_parameters[world_parameter];
+    (value - zone.0).min(zone.1 - value)
+}
+
+pub fn select_biome<'a>(
+    biome_generation: &'a HashMap<Biome, BiomeGeneration>,
+    world_parameters: &HashMap<WorldParameter, f32>,
+) -> Option<&'a Biome> {
+    biome_generation
+        .iter()
+        .filter_map(|(biome, biome_gen)| {
+            let mut total_score = 0.0;
+            for world_parameter in biome_gen.world_parameters.keys() {
+                let world_parameter_score = zone_score(
+                    biome_gen,
+                    world_parameter,
+                    world_parameters[world_parameter],
+                );
+                if world_parameter_score < 0.0 {
+                    return None;
+                } else {
+                    total_score += world_parameter_score;
+                }
+            }
+            Some((biome, total_score))
+        })
+        .min_by_key(|&(biome, score)| (r32(score), biome))
+        .map(|(biome, _)| biome)
+}
+
+// The biome whose zones are the least distance away from the parameters
+fn nearest_biome<'a>(
+    biome_generation: &'a HashMap<Biome, BiomeGeneration>,
+    world_parameters: &HashMap<WorldParameter, f32>,
+) -> Option<&'a Biome> {
+    biome_generation
+        .iter()
+        .map(|(biome, biome_gen)| {
+            let distance: f32 = biome_gen
+                .world_parameters
+                .keys()
+                .map(|world_parameter| {
+                    -zone_score(
+                        biome_gen,
+                        world_parameter,
+                        world_parameters[world_parameter],
+                    )
+                    .min(0.0)
+                })
+                .sum();
+            (biome, distance)
+        })
+        .min_by_key(|&(biome, distance)| (r32(distance), biome))
+        .map(|(biome, _)| biome)
+}
+
+// Stable across runs and platforms, unlike the rng or std's default hasher
+fn hash<T: std::hash::Hash>(obj: T) -> u64 {
+    use std::hash::*;
+    let mut hasher = siphasher::sip::SipHasher::new();
+    obj.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn choose_weighted<'a>(items: &[&'a ItemGeneration], roll: u64) -> Option<&'a ItemGeneration> {
+    let total_weight: u64 = items.iter().map(|item| item.weight as u64).sum();
+    if total_weight == 0 {
+        return None;
+    }
+    let mut roll = roll % total_weight;
+    for &item in items {
+        if roll < item.weight as u64 {
+            return Some(item);
+        }
+        roll -= item.weight as u64;
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::test_resource_pack;
+
+    fn generate(
+        world_gen: &WorldGen,
+    ) -> Vec<(Biome, Vec<(WorldParameter, f32)>, Option<EntityType>)> {
+        AABB::pos_size(vec2(-16, -16), vec2(32, 32))
+            .points()
+            .map(|pos| {
+                let tile = world_gen.generate_tile(pos);
+                let entity_type = world_gen.generate_entity(&tile, pos);
+                let mut world_parameters: Vec<(WorldParameter, f32)> =
+                    tile.world_parameters.into_iter().collect();
+                world_parameters.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+                (tile.biome, world_parameters, entity_type)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generation_is_deterministic() {
+        let resource_pack = test_resource_pack();
+        let first = generate(&WorldGen::new(42, None, &resource_pack));
+        let second = generate(&WorldGen::new(42, None, &resource_pack));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn seeds_generate_different_worlds() {
+        let resource_pack = test_resource_pack();
+        let first = generate(&WorldGen::new(1, None, &resource_pack));
+        let second = generate(&WorldGen::new(2, None, &resource_pack));
+        assert_ne!(first, second);
     }
 }
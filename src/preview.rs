@@ -0,0 +1,139 @@
+use super::*;
+
+#[derive(Clap)]
+pub struct PreviewOpt {
+    #[clap(long, default_value = "packs")]
+    packs: String,
+    #[clap(long, default_value = "0")]
+    seed: u32,
+    // Biome used where no generation biome matches, like fallback_biome in the world config
+    #[clap(long)]
+    fallback_biome: Option<String>,
+    #[clap(long, default_value = "0")]
+    x: i64,
+    #[clap(long, default_value = "0")]
+    y: i64,
+    #[clap(long, default_value = "256")]
+    width: u32,
+    #[clap(long, default_value = "256")]
+    height: u32,
+    #[clap(long)]
+    heatmap: Vec<String>,
+    #[clap(long, default_value = "preview.png")]
+    output: String,
+}
+
+#[derive(Debug, Clone)]
+struct UnknownParameterError {
+    parameter: String,
+}
+
+impl std::fmt::Display for UnknownParameterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown world parameter {}.", self.parameter)
+    }
+}
+
+impl std::error::Error for UnknownParameterError {}
+
+fn load_biome_rendering(
+    packs_path: &std::path::Path,
+    pack_list: &[String],
+) -> Result<HashMap<model::Biome, app::BiomeRendering>, anyhow::Error> {
+    let mut biomes = HashMap::new();
+    for pack in pack_list {
+        let path = packs_path.join(pack).join("client").join("biomes.json");
+        // Packs without client files add no colors, like in the game client
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let reader = std::io::BufReader::new(file);
+        let pack_biomes: HashMap<model::Biome, app::BiomeRendering> =
+            serde_json::from_reader(reader)?;
+        biomes.extend(pack_biomes);
+    }
+    Ok(biomes)
+}
+
+fn to_rgb(color: Color<f32>) -> image::Rgb<u8> {
+    image::Rgb([color.r, color.g, color.b].map(|x| (x.max(0.0).min(1.0) * 255.0) as u8))
+}
+
+pub fn run(opt: &PreviewOpt) -> Result<(), anyhow::Error> {
+    let packs_path = std::path::Path::new(&opt.packs);
+    let (pack_list, resource_pack) = model::ResourcePack::load_all(packs_path)?;
+    let biome_rendering = load_biome_rendering(packs_path, &pack_list)?;
+    let mut heatmaps = opt
+        .heatmap
+        .iter()
+        .map(|name| {
+            let parameter = model::WorldParameter(name.clone());
+            match resource_pack.world_parameters.get(&parameter) {
+                Some(properties) => Ok((
+                    parameter,
+                    properties.min_value,
+                    properties.max_value,
+                    image::RgbImage::new(opt.width, opt.height),
+                )),
+                None => Err(anyhow::Error::from(UnknownParameterError {
+                    parameter: name.clone(),
+                })),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let fallback_biome = opt.fallback_biome.clone().map(model::Biome::new);
+    if let Some(biome) = &fallback_biome {
+        if !resource_pack.biome_properties.contains_key(biome) {
+            return Err(anyhow::Error::from(model::UndefinedFallbackBiomeError {
+                biome: biome.clone(),
+            }));
+        }
+    }
+    let world_gen = model::WorldGen::new(opt.seed, fallback_biome, &resource_pack);
+
+    info!(
+        "Rendering {}x{} tiles at ({}, {}) with seed {}",
+        opt.width, opt.height, opt.x, opt.y, opt.seed
+    );
+    let mut missing_biomes = HashSet::new();
+    let mut biome_image = image::RgbImage::new(opt.width, opt.height);
+    for image_y in 0..opt.height {
+        for image_x in 0..opt.width {
+            // Images go top to bottom, the world goes bottom to top
+            let pos = vec2(
+                opt.x + image_x as i64,
+                opt.y + (opt.height - 1 - image_y) as i64,
+            );
+            let tile = world_gen.generate_tile(pos);
+            let color = match biome_rendering.get(&tile.biome) {
+                Some(rendering) => rendering.color,
+                None => {
+                    if missing_biomes.insert(tile.biome.clone()) {
+                        warn!("No color for biome {}", tile.biome);
+                    }
+                    Color::MAGENTA
+                }
+            };
+            biome_image.put_pixel(image_x, image_y, to_rgb(color));
+            for (parameter, min_value, max_value, image) in &mut heatmaps {
+                let value =
+                    (tile.world_parameters[parameter] - *min_value) / (*max_value - *min_value);
+                image.put_pixel(image_x, image_y, to_rgb(Color::rgb(value, value, value)));
+            }
+        }
+    }
+
+    let output = std::path::Path::new(&opt.output);
+    biome_image.save(output)?;
+    info!("Saved biomes to {:?}", output);
+    for (parameter, _, _, image) in heatmaps {
+        let mut file_name = output.file_stem().unwrap_or_default().to_owned();
+        file_name.push(format!("_{}.png", parameter.0));
+        let path = output.with_file_name(file_name);
+        image.save(&path)?;
+        info!("Saved {} heatmap to {:?}", parameter.0, path);
+    }
+    Ok(())
+}
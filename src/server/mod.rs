@@ -0,0 +1,143 @@
+use super::*;
+
+mod tick_stats;
+
+use tick_stats::*;
+
+const MAX_CATCH_UP_TICKS: usize = 5;
+
+struct Client {
+    player_id: Option<Id>,
+    server_model: Arc<Mutex<Model>>,
+    sender: Box<dyn geng::net::Sender<ServerMessage>>,
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if let Some(player_id) = self.player_id {
+            self.server_model.lock().unwrap().drop_player(player_id);
+        }
+    }
+}
+
+impl geng::net::Receiver<ClientMessage> for Client {
+    fn handle(&mut self, message: ClientMessage) {
+        let mut server_model = self.server_model.lock().unwrap();
+        match (self.player_id, message) {
+            (Some(player_id), message) => {
+                server_model.handle_message(player_id, message, &mut *self.sender);
+            }
+            (None, ClientMessage::Login { token }) => {
+                let player_id = server_model.spawn_player(&token);
+                self.sender.send(ServerMessage::PlayerId(player_id));
+                self.sender
+                    .send(ServerMessage::PackList(server_model.pack_list.clone()));
+                self.sender
+                    .send(ServerMessage::PackDataHash(server_model.pack_data_hash));
+                self.sender.send(ServerMessage::UpdateClientView(
+                    server_model.get_view(player_id),
+                ));
+                self.player_id = Some(player_id);
+            }
+            (None, message) => {
+                warn!(
+                    "Ignoring {:?} from a client that has not logged in",
+                    message
+                );
+            }
+        }
+    }
+}
+struct ServerApp {
+    model: Arc<Mutex<Model>>,
+}
+impl geng::net::server::App for ServerApp {
+    type Client = Client;
+    type ServerMessage = ServerMessage;
+    type ClientMessage = ClientMessage;
+    fn connect(&mut self, sender: Box<dyn geng::net::Sender<ServerMessage>>) -> Client {
+        Client {
+            server_model: self.model.clone(),
+            player_id: None,
+            sender,
+        }
+    }
+}
+
+pub struct Server {
+    model: Arc<Mutex<Model>>,
+    server: geng::net::Server<ServerApp>,
+}
+
+impl Server {
+    pub fn new<T: std::net::ToSocketAddrs + Debug + Copy>(addr: T, model: Model) -> Self {
+        let model = Arc::new(Mutex::new(model));
+        Self {
+            model: model.clone(),
+            server: geng::net::Server::new(
+                ServerApp {
+                    model: model.clone(),
+                },
+                addr,
+            ),
+        }
+    }
+    pub fn handle(&self) -> geng::net::ServerHandle {
+        self.server.handle()
+    }
+    pub fn run(self) {
+        let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let server_thread = std::thread::spawn({
+            let model = self.model;
+            let running = running.clone();
+            move || {
+                let mut timer = Timer::new();
+                let mut stats = TickStats::new();
+                let mut accumulator = 0.0;
+                let mut autosave_timer = Timer::new();
+                while running.load(std::sync::atomic::Ordering::Relaxed) {
+                    let tick_time = 1.0 / model.lock().unwrap().ticks_per_second as f64;
+                    accumulator += timer.tick();
+                    let mut ticks = 0;
+                    while accumulator >= tick_time {
+                        if ticks == MAX_CATCH_UP_TICKS {
+                            let skipped = (accumulator / tick_time) as usize;
+                            stats.record_skipped(skipped);
+                            accumulator -= skipped as f64 * tick_time;
+                            break;
+                        }
+                        let mut model = model.lock().unwrap();
+                        let tick_timer = Timer::new();
+                        model.tick();
+                        stats.record_tick(tick_timer.elapsed(), tick_time);
+                        accumulator -= tick_time;
+                        ticks += 1;
+                    }
+                    {
+                        let mut model = model.lock().unwrap();
+                        if autosave_timer.elapsed() >= model.autosave_interval as f64 {
+                            autosave_timer = Timer::new();
+                            debug!("Autosaving");
+                            model.autosave();
+                        }
+                    }
+                    if let Some(report) = stats.report() {
+                        if report.is_lagging() {
+                            warn!("Server is lagging. {}", report);
+                        } else {
+                            debug!("{}", report);
+                        }
+                        model.lock().unwrap().tick_report = Some(report);
+                    }
+                    std::thread::sleep(std::time::Duration::from_secs_f64(
+                        (tick_time - accumulator).max(0.0),
+                    ));
+                }
+                model.lock().unwrap().autosave();
+            }
+        });
+        self.server.run();
+        running.store(false, std::sync::atomic::Ordering::Relaxed);
+        server_thread.join().expect("Failed to join server thread");
+    }
+}
@@ -0,0 +1,55 @@
+use super::*;
+
+const REPORT_INTERVAL: f64 = 10.0;
+
+pub struct TickStats {
+    timer: Timer,
+    ticks: usize,
+    total_tick_time: f64,
+    max_tick_time: f64,
+    overruns: usize,
+    skipped_ticks: usize,
+}
+
+impl TickStats {
+    pub fn new() -> Self {
+        Self {
+            timer: Timer::new(),
+            ticks: 0,
+            total_tick_time: 0.0,
+            max_tick_time: 0.0,
+            overruns: 0,
+            skipped_ticks: 0,
+        }
+    }
+    pub fn record_tick(&mut self, tick_time: f64, tick_budget: f64) {
+        self.ticks += 1;
+        self.total_tick_time += tick_time;
+        self.max_tick_time = self.max_tick_time.max(tick_time);
+        if tick_time > tick_budget {
+            self.overruns += 1;
+        }
+    }
+    pub fn record_skipped(&mut self, ticks: usize) {
+        self.skipped_ticks += ticks;
+    }
+    pub fn report(&mut self) -> Option<model::TickReport> {
+        let elapsed = self.timer.elapsed();
+        if elapsed < REPORT_INTERVAL {
+            return None;
+        }
+        let report = model::TickReport {
+            ticks_per_second: self.ticks as f64 / elapsed,
+            average_tick_time: if self.ticks == 0 {
+                0.0
+            } else {
+                self.total_tick_time / self.ticks as f64
+            },
+            max_tick_time: self.max_tick_time,
+            overruns: self.overruns,
+            skipped_ticks: self.skipped_ticks,
+        };
+        *self = Self::new();
+        Some(report)
+    }
+}
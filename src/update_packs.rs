@@ -0,0 +1,29 @@
+use super::*;
+
+#[derive(Clap)]
+pub struct UpdatePacksOpt {
+    #[clap(long, default_value = "new_world")]
+    world: String,
+    #[clap(long, default_value = "packs")]
+    packs: String,
+    #[clap(long)]
+    delete_orphans: bool,
+    #[clap(long)]
+    replace_orphans: Option<String>,
+}
+
+pub fn run(opt: &UpdatePacksOpt) -> Result<(), anyhow::Error> {
+    let policy = match (&opt.replace_orphans, opt.delete_orphans) {
+        (Some(_), true) => {
+            return Err(anyhow!(
+                "--delete-orphans and --replace-orphans can not be used together"
+            ))
+        }
+        (Some(entity_type), false) => {
+            model::OrphanPolicy::Replace(model::EntityType(entity_type.clone()))
+        }
+        (None, true) => model::OrphanPolicy::Delete,
+        (None, false) => model::OrphanPolicy::Report,
+    };
+    Model::update_packs(&opt.world, &opt.packs, policy)
+}
@@ -0,0 +1,24 @@
+use super::*;
+
+// Writes to a temporary file first, so the file is either fully replaced or left as it was
+pub fn write_atomic(
+    path: impl AsRef<std::path::Path>,
+    write: impl FnOnce(&mut std::io::BufWriter<std::fs::File>) -> Result<(), anyhow::Error>,
+) -> Result<(), anyhow::Error> {
+    let path = path.as_ref();
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(&temp_path)?);
+    write(&mut writer)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+// In seconds, 0 if the clock is before the epoch
+pub fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
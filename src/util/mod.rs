@@ -1,11 +1,19 @@
 use super::*;
 
+mod atomic;
 mod div;
 mod iter;
 mod loaded;
+mod merge_patch;
+mod region;
 mod saved;
+mod versioned;
 
+pub use atomic::*;
 pub use div::*;
 pub use iter::*;
 pub use loaded::*;
+pub use merge_patch::*;
+pub use region::*;
 pub use saved::*;
+pub use versioned::*;
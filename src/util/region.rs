@@ -0,0 +1,275 @@
+use super::*;
+
+// A region file groups REGION_SIZE x REGION_SIZE chunks:
+// magic, format version, then an offset table with (offset, length) for
+// every chunk in the region, followed by the zlib-compressed chunk data.
+// Chunks that were never saved have a zero offset.
+const MAGIC: &[u8; 4] = b"ISRG";
+const REGION_FORMAT_VERSION: u32 = 1;
+const REGION_SIZE: i64 = 16;
+const TABLE_ENTRIES: usize = (REGION_SIZE * REGION_SIZE) as usize;
+const HEADER_SIZE: usize = 8 + TABLE_ENTRIES * 8;
+
+#[derive(Debug, Clone)]
+struct InvalidRegionError {
+    path: std::path::PathBuf,
+}
+
+impl std::fmt::Display for InvalidRegionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} is not a valid region file.", self.path)
+    }
+}
+
+impl std::error::Error for InvalidRegionError {}
+
+// Chunks that were not written, since their region files could not be read
+#[derive(Debug, Clone)]
+pub struct UnwritableChunksError {
+    pub chunk_positions: Vec<Vec2<i64>>,
+}
+
+impl std::fmt::Display for UnwritableChunksError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} chunks were not saved, their region files could not be read.",
+            self.chunk_positions.len()
+        )
+    }
+}
+
+impl std::error::Error for UnwritableChunksError {}
+
+fn region_pos(chunk_pos: Vec2<i64>) -> Vec2<i64> {
+    chunk_pos.map(|x| div_down(x, REGION_SIZE))
+}
+
+fn region_path(dir: &std::path::Path, region_pos: Vec2<i64>) -> std::path::PathBuf {
+    dir.join(format!("region_{}_{}.region", region_pos.x, region_pos.y))
+}
+
+fn table_index(chunk_pos: Vec2<i64>) -> usize {
+    let local = chunk_pos - region_pos(chunk_pos) * REGION_SIZE;
+    (local.y * REGION_SIZE + local.x) as usize
+}
+
+fn read_header(
+    path: &std::path::Path,
+    mut reader: impl std::io::Read,
+) -> Result<Vec<(u32, u32)>, anyhow::Error> {
+    let mut header = vec![0; HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+    let read_u32 = |offset: usize| {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&header[offset..offset + 4]);
+        u32::from_le_bytes(bytes)
+    };
+    if &header[0..4] != MAGIC || read_u32(4) != REGION_FORMAT_VERSION {
+        return Err(anyhow::Error::from(InvalidRegionError {
+            path: path.to_owned(),
+        }));
+    }
+    Ok((0..TABLE_ENTRIES)
+        .map(|i| (read_u32(8 + i * 8), read_u32(12 + i * 8)))
+        .collect())
+}
+
+fn encode<T: Versioned>(value: &T) -> Result<Vec<u8>, anyhow::Error> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    write_versioned(&mut encoder, value)?;
+    Ok(encoder.finish()?)
+}
+
+pub fn read_chunk<T: Versioned>(
+    dir: impl AsRef<std::path::Path>,
+    chunk_pos: Vec2<i64>,
+) -> Result<Option<T>, anyhow::Error> {
+    use std::io::{Read, Seek};
+    let path = region_path(dir.as_ref(), region_pos(chunk_pos));
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut file = std::io::BufReader::new(std::fs::File::open(&path)?);
+    let (offset, length) = read_header(&path, &mut file)?[table_index(chunk_pos)];
+    if offset == 0 {
+        return Ok(None);
+    }
+    file.seek(std::io::SeekFrom::Start(offset as u64))?;
+    let decoder = flate2::read::ZlibDecoder::new(file.take(length as u64));
+    Ok(Some(read_versioned(decoder)?))
+}
+
+fn read_region(path: &std::path::Path) -> Result<Vec<Option<Vec<u8>>>, anyhow::Error> {
+    let data = std::fs::read(path)?;
+    let table = read_header(path, &data[..])?;
+    table
+        .into_iter()
+        .map(|(offset, length)| {
+            if offset == 0 {
+                return Ok(None);
+            }
+            let (start, end) = (offset as usize, offset as usize + length as usize);
+            if end > data.len() {
+                return Err(anyhow::Error::from(InvalidRegionError {
+                    path: path.to_owned(),
+                }));
+            }
+            Ok(Some(data[start..end].to_vec()))
+        })
+        .collect()
+}
+
+fn write_region(path: &std::path::Path, chunks: &[Option<Vec<u8>>]) -> Result<(), anyhow::Error> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&REGION_FORMAT_VERSION.to_le_bytes());
+    let mut offset = HEADER_SIZE;
+    for chunk in chunks {
+        let (chunk_offset, length) = match chunk {
+            Some(data) => (offset, data.len()),
+            None => (0, 0),
+        };
+        header.extend_from_slice(&(chunk_offset as u32).to_le_bytes());
+        header.extend_from_slice(&(length as u32).to_le_bytes());
+        offset += length;
+    }
+    write_atomic(path, |writer| {
+        writer.write_all(&header)?;
+        for data in chunks.iter().flatten() {
+            writer.write_all(data)?;
+        }
+        Ok(())
+    })
+}
+
+// Rewrites every affected region file once, keeping the chunks not being saved.
+// Regions that can not be read are left as they are, without the new chunks.
+pub fn write_chunks<'a, T: Versioned + 'a>(
+    dir: impl AsRef<std::path::Path>,
+    chunks: impl IntoIterator<Item = (Vec2<i64>, &'a T)>,
+) -> Result<(), anyhow::Error> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let mut regions: HashMap<Vec2<i64>, Vec<(Vec2<i64>, &T)>> = HashMap::new();
+    for (chunk_pos, chunk) in chunks {
+        regions
+            .entry(region_pos(chunk_pos))
+            .or_default()
+            .push((chunk_pos, chunk));
+    }
+    let mut unwritable = Vec::new();
+    for (region_pos, chunks) in regions {
+        let path = region_path(dir, region_pos);
+        let mut region = if path.exists() {
+            match read_region(&path) {
+                Ok(region) => region,
+                Err(e) => {
+                    error!(
+                        "Failed to read {:?}, not saving {} chunks to it: {}",
+                        path,
+                        chunks.len(),
+                        e
+                    );
+                    unwritable.extend(chunks.iter().map(|&(chunk_pos, _)| chunk_pos));
+                    continue;
+                }
+            }
+        } else {
+            vec![None; TABLE_ENTRIES]
+        };
+        for (chunk_pos, chunk) in chunks {
+            region[table_index(chunk_pos)] = Some(encode(chunk)?);
+        }
+        write_region(&path, &region)?;
+    }
+    if !unwritable.is_empty() {
+        return Err(anyhow::Error::from(UnwritableChunksError {
+            chunk_positions: unwritable,
+        }));
+    }
+    Ok(())
+}
+
+fn region_files(
+    dir: &std::path::Path,
+) -> Result<Vec<(Vec2<i64>, std::path::PathBuf)>, anyhow::Error> {
+    let mut region_files = Vec::new();
+    if !dir.exists() {
+        return Ok(region_files);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let region_pos = match path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("region_"))
+            .and_then(|name| name.strip_suffix(".region"))
+            .and_then(|name| {
+                let (x, y) = name.split_at(name.rfind('_')?);
+                Some(vec2(x.parse().ok()?, y[1..].parse().ok()?))
+            }) {
+            Some(region_pos) => region_pos,
+            None => continue,
+        };
+        region_files.push((region_pos, path));
+    }
+    Ok(region_files)
+}
+
+// Positions of all chunks stored in the region files of a directory
+pub fn saved_chunk_positions(
+    dir: impl AsRef<std::path::Path>,
+) -> Result<Vec<Vec2<i64>>, anyhow::Error> {
+    let mut chunk_positions = Vec::new();
+    for (region_pos, path) in region_files(dir.as_ref())? {
+        let file = std::io::BufReader::new(std::fs::File::open(&path)?);
+        for (index, (offset, _)) in read_header(&path, file)?.into_iter().enumerate() {
+            if offset != 0 {
+                let index = index as i64;
+                chunk_positions.push(
+                    region_pos * REGION_SIZE + vec2(index % REGION_SIZE, index / REGION_SIZE),
+                );
+            }
+        }
+    }
+    Ok(chunk_positions)
+}
+
+// Fails if any chunk was saved in a format newer than T supports.
+// Unreadable regions and chunks are left to the loading code.
+pub fn check_chunk_versions<T: Versioned>(
+    dir: impl AsRef<std::path::Path>,
+) -> Result<(), anyhow::Error> {
+    for (_, path) in region_files(dir.as_ref())? {
+        let region = match read_region(&path) {
+            Ok(region) => region,
+            Err(_) => continue,
+        };
+        for data in region.iter().flatten() {
+            if let Ok(version) = read_version(flate2::read::ZlibDecoder::new(&data[..])) {
+                check_version::<T>(version)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Copies the region file holding a chunk to the quarantine directory
+pub fn quarantine_region(
+    dir: impl AsRef<std::path::Path>,
+    quarantine_dir: impl AsRef<std::path::Path>,
+    chunk_pos: Vec2<i64>,
+) -> Result<std::path::PathBuf, anyhow::Error> {
+    let path = region_path(dir.as_ref(), region_pos(chunk_pos));
+    let quarantine_dir = quarantine_dir.as_ref();
+    std::fs::create_dir_all(quarantine_dir)?;
+    let backup_path = quarantine_dir.join(format!(
+        "chunk_{}_{}_{}.region",
+        chunk_pos.x,
+        chunk_pos.y,
+        unix_time()
+    ));
+    std::fs::copy(&path, &backup_path)?;
+    Ok(backup_path)
+}
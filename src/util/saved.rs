@@ -1,41 +1,85 @@
 use super::*;
 
-#[derive(Deref, DerefMut)]
-pub struct Saved<T: Serialize + for<'de> Deserialize<'de>> {
+pub struct Saved<T: Versioned> {
     path: std::path::PathBuf,
-    #[deref]
-    #[deref_mut]
     value: T,
+    changed: Cell<bool>,
 }
 
-impl<T: Serialize + for<'de> Deserialize<'de>> Saved<T> {
+impl<T: Versioned> Saved<T> {
     pub fn new(path: impl AsRef<std::path::Path>, default: impl FnOnce() -> T) -> Self {
         let path = path.as_ref();
-        match Self::load(path) {
-            Ok(result) => result,
-            Err(_) => Self {
-                path: path.to_owned(),
-                value: default(),
-            },
+        if path.exists() {
+            match Self::load(path) {
+                Ok(result) => return result,
+                Err(e) => {
+                    let mut backup_path = path.as_os_str().to_owned();
+                    backup_path.push(".corrupt");
+                    error!(
+                        "Failed to load {:?}, moving it to {:?}: {}",
+                        path, backup_path, e
+                    );
+                    if let Err(e) = std::fs::rename(path, &backup_path) {
+                        error!("Failed to move {:?}: {}", path, e);
+                    }
+                }
+            }
+        }
+        Self {
+            path: path.to_owned(),
+            value: default(),
+            changed: Cell::new(true),
+        }
+    }
+    // Replaces the saved file on the next save
+    pub fn with_value(path: impl AsRef<std::path::Path>, value: T) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            value,
+            changed: Cell::new(true),
         }
     }
     pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, anyhow::Error> {
         let path = path.as_ref().to_owned();
         let file = std::fs::File::open(&path)?;
         let reader = std::io::BufReader::new(file);
-        let value = bincode::deserialize_from(reader)?;
-        Ok(Self { path, value })
+        let value = read_versioned(reader)?;
+        Ok(Self {
+            path,
+            value,
+            changed: Cell::new(false),
+        })
     }
     pub fn save(&self) -> Result<(), anyhow::Error> {
-        let file = std::fs::File::create(&self.path)?;
-        let writer = std::io::BufWriter::new(file);
-        bincode::serialize_into(writer, &self.value)?;
+        write_atomic(&self.path, |writer| write_versioned(writer, &self.value))?;
+        self.changed.set(false);
         Ok(())
     }
+    pub fn autosave(&self) {
+        if self.changed.get() {
+            if let Err(e) = self.save() {
+                error!("Failed to save {:?}: {}", self.path, e);
+            }
+        }
+    }
+}
+
+impl<T: Versioned> Deref for Saved<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Versioned> DerefMut for Saved<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.changed.set(true);
+        &mut self.value
+    }
 }
 
-impl<T: Serialize + for<'de> Deserialize<'de>> Drop for Saved<T> {
+impl<T: Versioned> Drop for Saved<T> {
     fn drop(&mut self) {
-        self.save().unwrap();
+        self.autosave();
     }
 }
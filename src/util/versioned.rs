@@ -0,0 +1,131 @@
+use super::*;
+
+const MAGIC: &[u8; 4] = b"ISLE";
+
+// Files saved before versioning have no header and are treated as version 0
+pub trait Versioned: Serialize + for<'de> Deserialize<'de> {
+    const VERSION: u32;
+    fn migrate(version: u32, data: &[u8]) -> Result<Self, anyhow::Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct NewerFormatError {
+    version: u32,
+    supported: u32,
+}
+
+impl std::fmt::Display for NewerFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Save format version {} is newer than the latest supported version {}.",
+            self.version, self.supported
+        )
+    }
+}
+
+impl std::error::Error for NewerFormatError {}
+
+fn decode<T: Versioned>(
+    version: u32,
+    data: &[u8],
+    current: impl FnOnce(&[u8]) -> Result<T, anyhow::Error>,
+) -> Result<T, anyhow::Error> {
+    if version == T::VERSION {
+        current(data)
+    } else if version > T::VERSION {
+        Err(anyhow::Error::from(NewerFormatError {
+            version,
+            supported: T::VERSION,
+        }))
+    } else {
+        debug!("Migrating save data from format version {}", version);
+        T::migrate(version, data)
+    }
+}
+
+pub fn write_versioned<T: Versioned>(
+    mut writer: impl std::io::Write,
+    value: &T,
+) -> Result<(), anyhow::Error> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&T::VERSION.to_le_bytes())?;
+    rmp_serde::encode::write_named(&mut writer, value)?;
+    Ok(())
+}
+
+// Version from the header of a save, without reading the rest of it
+pub fn read_version(reader: impl std::io::Read) -> std::io::Result<u32> {
+    use std::io::Read;
+    let mut header = Vec::new();
+    reader.take(8).read_to_end(&mut header)?;
+    if header.len() == 8 && header.starts_with(MAGIC) {
+        let mut version = [0; 4];
+        version.copy_from_slice(&header[4..8]);
+        Ok(u32::from_le_bytes(version))
+    } else {
+        Ok(0)
+    }
+}
+
+pub fn check_version<T: Versioned>(version: u32) -> Result<(), anyhow::Error> {
+    if version > T::VERSION {
+        return Err(anyhow::Error::from(NewerFormatError {
+            version,
+            supported: T::VERSION,
+        }));
+    }
+    Ok(())
+}
+
+pub fn read_versioned<T: Versioned>(mut reader: impl std::io::Read) -> Result<T, anyhow::Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let (version, data) = if data.len() >= 8 && data.starts_with(MAGIC) {
+        let mut version = [0; 4];
+        version.copy_from_slice(&data[4..8]);
+        (u32::from_le_bytes(version), &data[8..])
+    } else {
+        (0, &data[..])
+    };
+    decode(version, data, |data| Ok(rmp_serde::from_slice(data)?))
+}
+
+pub fn save_json<T: Versioned>(
+    path: impl AsRef<std::path::Path>,
+    value: &T,
+) -> Result<(), anyhow::Error> {
+    #[derive(Serialize)]
+    struct JsonSave<'a, T> {
+        format_version: u32,
+        data: &'a T,
+    }
+    write_atomic(path, |writer| {
+        serde_json::to_writer_pretty(
+            writer,
+            &JsonSave {
+                format_version: T::VERSION,
+                data: value,
+            },
+        )?;
+        Ok(())
+    })
+}
+
+pub fn load_json<T: Versioned>(path: impl AsRef<std::path::Path>) -> Result<T, anyhow::Error> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let value: serde_json::Value = serde_json::from_reader(reader)?;
+    let (version, data) = match value {
+        serde_json::Value::Object(mut object) if object.contains_key("format_version") => {
+            let version = serde_json::from_value(object.remove("format_version").unwrap())?;
+            let data = object
+                .remove("data")
+                .ok_or_else(|| anyhow!("Missing data in versioned save"))?;
+            (version, data)
+        }
+        value => (0, value),
+    };
+    decode(version, &serde_json::to_vec(&data)?, |data| {
+        Ok(serde_json::from_slice(data)?)
+    })
+}
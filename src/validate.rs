@@ -0,0 +1,506 @@
+use super::*;
+
+use model::{Biome, EntityType, WorldParameter};
+
+#[derive(Clap)]
+pub struct ValidateOpt {
+    #[clap(long, default_value = "packs")]
+    packs: String,
+    // World config to check against the packs
+    #[clap(long)]
+    config: Option<String>,
+}
+
+struct Problem {
+    path: std::path::PathBuf,
+    key: String,
+    message: String,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.key.is_empty() {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        } else {
+            write!(f, "{}: {}: {}", self.path.display(), self.key, self.message)
+        }
+    }
+}
+
+#[derive(Default)]
+struct PackFiles {
+    biome_properties: HashMap<Biome, model::BiomeProperties>,
+    biome_generation: HashMap<Biome, model::BiomeGeneration>,
+    world_parameters: HashMap<WorldParameter, model::MultiNoiseProperties>,
+    entity_components: HashMap<EntityType, model::EntityComponents>,
+    entity_generation: HashMap<Biome, Vec<model::ItemGeneration>>,
+    recipes: Vec<model::Recipe>,
+    loot_tables: HashMap<String, model::LootTable>,
+    patches: model::PackPatches,
+    client_biomes: HashMap<Biome, app::BiomeRendering>,
+    client_entity_components: HashMap<EntityType, app::ClientEntityComponents>,
+    client_models: HashMap<EntityType, app::EntityInfo>,
+}
+
+#[derive(Default)]
+struct Validator {
+    errors: Vec<Problem>,
+    warnings: Vec<Problem>,
+}
+
+fn biome_key(biome: &Biome) -> String {
+    format!("[{:?}]", biome.to_string())
+}
+
+fn entity_key(entity_type: &EntityType) -> String {
+    format!("[{:?}]", entity_type.0)
+}
+
+impl Validator {
+    fn error(&mut self, path: &std::path::Path, key: String, message: String) {
+        self.errors.push(Problem {
+            path: path.to_owned(),
+            key,
+            message,
+        });
+    }
+    fn warning(&mut self, path: &std::path::Path, key: String, message: String) {
+        self.warnings.push(Problem {
+            path: path.to_owned(),
+            key,
+            message,
+        });
+    }
+    fn read<T: Default + for<'de> Deserialize<'de>>(&mut self, path: &std::path::Path) -> T {
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return default(),
+            Err(e) => {
+                self.error(path, String::new(), e.to_string());
+                return default();
+            }
+        };
+        match serde_json::from_reader(std::io::BufReader::new(file)) {
+            Ok(value) => value,
+            Err(e) => {
+                self.error(path, String::new(), e.to_string());
+                default()
+            }
+        }
+    }
+    fn read_pack(&mut self, path: &std::path::Path) -> PackFiles {
+        let server_path = path.join("server");
+        let client_path = path.join("client");
+        PackFiles {
+            biome_properties: self.read(&server_path.join("biomes.json")),
+            biome_generation: self.read(&server_path.join("generation-biomes.json")),
+            world_parameters: self.read(&server_path.join("world-parameters.json")),
+            entity_components: self.read(&server_path.join("entities.json")),
+            entity_generation: self.read(&server_path.join("generation-entities.json")),
+            recipes: self.read(&server_path.join("recipes.json")),
+            loot_tables: self.read(&server_path.join("loot-tables.json")),
+            patches: model::PackPatches {
+                recipes: self.read(&server_path.join("recipe-patches.json")),
+                entities: self.read(&server_path.join("entity-patches.json")),
+            },
+            client_biomes: self.read(&client_path.join("biomes.json")),
+            client_entity_components: self.read(&client_path.join("entities.json")),
+            client_models: self.read(&client_path.join("models.json")),
+        }
+    }
+}
+
+// Everything defined by all packs together, since packs may reference each other
+#[derive(Default)]
+struct Definitions {
+    biomes: HashSet<Biome>,
+    world_parameters: HashSet<WorldParameter>,
+    entity_types: HashSet<EntityType>,
+    recipes: HashSet<String>,
+    loot_tables: HashSet<String>,
+    client_biomes: HashSet<Biome>,
+    client_entity_types: HashSet<EntityType>,
+    client_models: HashSet<EntityType>,
+}
+
+impl Definitions {
+    fn add(&mut self, pack: &PackFiles) {
+        self.biomes.extend(pack.biome_properties.keys().cloned());
+        self.world_parameters
+            .extend(pack.world_parameters.keys().cloned());
+        self.entity_types
+            .extend(pack.entity_components.keys().cloned());
+        self.loot_tables.extend(pack.loot_tables.keys().cloned());
+        self.recipes.extend(pack.recipes.iter().map(|recipe| {
+            if recipe.id.is_empty() {
+                recipe.default_id()
+            } else {
+                recipe.id.clone()
+            }
+        }));
+        self.client_biomes
+            .extend(pack.client_biomes.keys().cloned());
+        self.client_entity_types
+            .extend(pack.client_entity_components.keys().cloned());
+        self.client_models
+            .extend(pack.client_models.keys().cloned());
+    }
+}
+
+impl Validator {
+    fn check_biome(
+        &mut self,
+        definitions: &Definitions,
+        path: &std::path::Path,
+        key: String,
+        biome: &Biome,
+    ) {
+        if !definitions.biomes.contains(biome) {
+            self.error(
+                path,
+                key,
+                format!("biome {} is not defined in any server/biomes.json", biome),
+            );
+        }
+    }
+    fn check_loot_table(
+        &mut self,
+        definitions: &Definitions,
+        path: &std::path::Path,
+        key: String,
+        loot_table: &str,
+    ) {
+        if !definitions.loot_tables.contains(loot_table) {
+            self.error(
+                path,
+                key,
+                format!(
+                    "loot table {} is not defined in any server/loot-tables.json",
+                    loot_table
+                ),
+            );
+        }
+    }
+    fn check_time_range(
+        &mut self,
+        path: &std::path::Path,
+        key: String,
+        time_range: &model::TimeRange,
+    ) {
+        if !time_range.is_valid() {
+            self.error(path, key, "times of day must be between 0 and 1".to_owned());
+        }
+    }
+    fn check_entity_type(
+        &mut self,
+        definitions: &Definitions,
+        path: &std::path::Path,
+        key: String,
+        entity_type: &EntityType,
+    ) {
+        if !definitions.entity_types.contains(entity_type) {
+            self.error(
+                path,
+                key,
+                format!(
+                    "entity type {} is not defined in any server/entities.json",
+                    entity_type
+                ),
+            );
+        }
+    }
+    fn check_pack(&mut self, definitions: &Definitions, path: &std::path::Path, pack: &PackFiles) {
+        let server_path = path.join("server");
+        let client_path = path.join("client");
+
+        let file = server_path.join("biomes.json");
+        for biome in pack.biome_properties.keys() {
+            if !definitions.client_biomes.contains(biome) {
+                self.error(
+                    &file,
+                    biome_key(biome),
+                    format!("biome {} has no color in any client/biomes.json", biome),
+                );
+            }
+        }
+
+        let file = server_path.join("generation-biomes.json");
+        for (biome, biome_gen) in &pack.biome_generation {
+            self.check_biome(definitions, &file, biome_key(biome), biome);
+            for (parameter, zone) in &biome_gen.world_parameters {
+                let key = format!("{}.world_parameters[{:?}]", biome_key(biome), parameter.0);
+                if !definitions.world_parameters.contains(parameter) {
+                    self.error(
+                        &file,
+                        key.clone(),
+                        format!(
+                            "world parameter {} is not defined in any server/world-parameters.json",
+                            parameter.0
+                        ),
+                    );
+                }
+                if zone.0 > zone.1 {
+                    self.error(&file, key, format!("empty range {:?}", zone));
+                }
+            }
+        }
+
+        let file = server_path.join("world-parameters.json");
+        for (parameter, properties) in &pack.world_parameters {
+            if properties.min_value > properties.max_value {
+                self.error(
+                    &file,
+                    format!("[{:?}]", parameter.0),
+                    "min_value is greater than max_value".to_owned(),
+                );
+            }
+        }
+
+        let file = server_path.join("generation-entities.json");
+        for (biome, items) in &pack.entity_generation {
+            self.check_biome(definitions, &file, biome_key(biome), biome);
+            for (index, item) in items.iter().enumerate() {
+                if let Some(entity_type) = &item.entity_type {
+                    let key = format!("{}[{}].entity_type", biome_key(biome), index);
+                    self.check_entity_type(definitions, &file, key, entity_type);
+                }
+                if let Some(time_of_day) = &item.time_of_day {
+                    let key = format!("{}[{}].time_of_day", biome_key(biome), index);
+                    self.check_time_range(&file, key, time_of_day);
+                }
+            }
+        }
+
+        let file = server_path.join("recipes.json");
+        for (index, recipe) in pack.recipes.iter().enumerate() {
+            for (field, entity_type) in &[
+                ("ingredient1", &recipe.ingredient1),
+                ("ingredient2", &recipe.ingredient2),
+                ("result1", &recipe.result1),
+                ("result2", &recipe.result2),
+            ] {
+                if let Some(entity_type) = entity_type {
+                    let key = format!("[{}].{}", index, field);
+                    self.check_entity_type(definitions, &file, key, entity_type);
+                }
+            }
+            if let Some(biome) = &recipe.conditions {
+                let key = format!("[{}].conditions", index);
+                self.check_biome(definitions, &file, key, biome);
+            }
+            if let Some(loot_table) = &recipe.loot_table {
+                let key = format!("[{}].loot_table", index);
+                self.check_loot_table(definitions, &file, key, loot_table);
+            }
+            if let Some(time_of_day) = &recipe.time_of_day {
+                let key = format!("[{}].time_of_day", index);
+                self.check_time_range(&file, key, time_of_day);
+            }
+            // The worn tool stays in the hand, so result1 could never be crafted
+            if recipe.durability_cost.is_some()
+                && (recipe.ingredient1.is_none() || recipe.result1 != recipe.ingredient1)
+            {
+                self.error(
+                    &file,
+                    format!("[{}].durability_cost", index),
+                    "durability_cost requires result1 to be the same as ingredient1".to_owned(),
+                );
+            }
+        }
+
+        let file = server_path.join("loot-tables.json");
+        for (id, loot_table) in &pack.loot_tables {
+            for (pool_index, pool) in loot_table.pools.iter().enumerate() {
+                let pool_key = format!("[{:?}].pools[{}]", id, pool_index);
+                if pool.rolls.0 > pool.rolls.1 {
+                    self.error(
+                        &file,
+                        format!("{}.rolls", pool_key),
+                        "empty range".to_owned(),
+                    );
+                }
+                for (entry_index, entry) in pool.entries.iter().enumerate() {
+                    let entry_key = format!("{}.entries[{}]", pool_key, entry_index);
+                    if let Some(entity_type) = &entry.entity_type {
+                        let key = format!("{}.entity_type", entry_key);
+                        self.check_entity_type(definitions, &file, key, entity_type);
+                    }
+                    if entry.count.0 > entry.count.1 {
+                        self.error(
+                            &file,
+                            format!("{}.count", entry_key),
+                            "empty range".to_owned(),
+                        );
+                    }
+                }
+            }
+        }
+
+        let file = server_path.join("recipe-patches.json");
+        for id in pack.patches.recipes.keys() {
+            if !definitions.recipes.contains(id) {
+                self.warning(
+                    &file,
+                    format!("[{:?}]", id),
+                    format!("recipe {} is not defined in any server/recipes.json", id),
+                );
+            }
+        }
+
+        let file = server_path.join("entity-patches.json");
+        for entity_type in pack.patches.entities.keys() {
+            self.check_entity_type(definitions, &file, entity_key(entity_type), entity_type);
+        }
+
+        let file = server_path.join("entities.json");
+        for (entity_type, components) in &pack.entity_components {
+            let key = entity_key(entity_type);
+            if let Some(model::CompController::BiomeRandomWalker { biome, active_time }) =
+                &components.controller
+            {
+                let walker_key = format!("{}.controller.BiomeRandomWalker", key);
+                self.check_biome(definitions, &file, format!("{}.biome", walker_key), biome);
+                if let Some(active_time) = active_time {
+                    let key = format!("{}.active_time", walker_key);
+                    self.check_time_range(&file, key, active_time);
+                }
+            }
+            if let Some(loot_table) = &components.loot_table {
+                let key = format!("{}.loot_table", key);
+                self.check_loot_table(definitions, &file, key, loot_table);
+            }
+            if let Some(broken_result) = components
+                .durability
+                .as_ref()
+                .and_then(|durability| durability.broken_result.as_ref())
+            {
+                let key = format!("{}.durability.broken_result", key);
+                self.check_entity_type(definitions, &file, key, broken_result);
+            }
+            if let Some(container) = &components.container {
+                for (index, allowed_type) in container.allowed_types.iter().flatten().enumerate() {
+                    let key = format!("{}.container.allowed_types[{}]", key, index);
+                    self.check_entity_type(definitions, &file, key, allowed_type);
+                }
+            }
+            if !definitions.client_entity_types.contains(entity_type) {
+                self.error(
+                    &file,
+                    key.clone(),
+                    format!(
+                        "entity type {} is not defined in any client/entities.json",
+                        entity_type
+                    ),
+                );
+            }
+            if !definitions.client_models.contains(entity_type) {
+                let message = format!(
+                    "entity type {} has no model in any client/models.json",
+                    entity_type
+                );
+                // Held items are always drawn with their model
+                if components.pickable.is_some() {
+                    self.error(&file, key, message);
+                } else {
+                    self.warning(&file, key, message);
+                }
+            }
+        }
+
+        let file = client_path.join("biomes.json");
+        for biome in pack.client_biomes.keys() {
+            if !definitions.biomes.contains(biome) {
+                self.warning(
+                    &file,
+                    biome_key(biome),
+                    format!("biome {} is not used by the server", biome),
+                );
+            }
+        }
+
+        let file = client_path.join("entities.json");
+        for entity_type in pack.client_entity_components.keys() {
+            if !definitions.entity_types.contains(entity_type) {
+                self.warning(
+                    &file,
+                    entity_key(entity_type),
+                    format!("entity type {} is not used by the server", entity_type),
+                );
+            }
+        }
+
+        let file = client_path.join("models.json");
+        for (entity_type, entity_info) in &pack.client_models {
+            let key = entity_key(entity_type);
+            if !definitions.entity_types.contains(entity_type) {
+                self.warning(
+                    &file,
+                    key.clone(),
+                    format!("entity type {} is not used by the server", entity_type),
+                );
+            }
+            let model_path = client_path.join("entities").join(&entity_info.model);
+            if !model_path.is_file() {
+                self.error(
+                    &file,
+                    format!("{}.model", key),
+                    format!("model file {} does not exist", model_path.display()),
+                );
+            }
+        }
+    }
+}
+
+// Returns whether all packs are valid
+pub fn run(opt: &ValidateOpt) -> Result<bool, anyhow::Error> {
+    let pack_paths: Vec<std::path::PathBuf> = model::PackManifest::load_order(&opt.packs)?
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
+
+    let mut validator = Validator::default();
+    let mut definitions = Definitions::default();
+    let packs: Vec<PackFiles> = pack_paths
+        .iter()
+        .map(|path| validator.read_pack(path))
+        .collect();
+    for pack in &packs {
+        definitions.add(pack);
+    }
+    let player = EntityType("Player".to_owned());
+    if !definitions.entity_types.contains(&player) {
+        validator.error(
+            std::path::Path::new(&opt.packs),
+            String::new(),
+            "no pack defines the Player entity type".to_owned(),
+        );
+    }
+    for (path, pack) in pack_paths.iter().zip(&packs) {
+        validator.check_pack(&definitions, path, pack);
+    }
+    if let Some(path) = &opt.config {
+        let path = std::path::Path::new(path);
+        match util::load_json::<model::Config>(path) {
+            Ok(config) => {
+                if let Some(biome) = &config.fallback_biome {
+                    validator.check_biome(&definitions, path, "fallback_biome".to_owned(), biome);
+                }
+            }
+            Err(e) => validator.error(path, String::new(), e.to_string()),
+        }
+    }
+
+    for warning in &validator.warnings {
+        warn!("{}", warning);
+    }
+    for error in &validator.errors {
+        error!("{}", error);
+    }
+    info!(
+        "Checked {} packs: {} errors, {} warnings",
+        packs.len(),
+        validator.errors.len(),
+        validator.warnings.len()
+    );
+    Ok(validator.errors.is_empty())
+}